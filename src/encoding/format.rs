@@ -2,3 +2,254 @@
 //!
 //! Decodes and formats raw keys and values, recursively as needed. Handles both
 //! Raft, MVCC, SQL, and raw binary data.
+//!
+//! Formatters are composable: an outer formatter decodes its own key/value
+//! envelope and dispatches the inner bytes to a nested formatter. Only the raw
+//! and SQL formatters exist for now -- the Raft and MVCC formatters are added
+//! together with the key/value types they decode.
+
+use super::{bincode, Key as _};
+use crate::sql;
+use crate::sql::types::Value;
+
+use std::collections::BTreeSet;
+
+/// Formats encoded keys and values.
+pub trait Formatter {
+    /// Formats a key.
+    fn key(key: &[u8]) -> String;
+
+    /// Formats a value. Also takes the key to determine the kind of value.
+    fn value(key: &[u8], value: &[u8]) -> String;
+
+    /// Formats a key/value pair.
+    fn key_value(key: &[u8], value: &[u8]) -> String {
+        Self::key_maybe_value(key, Some(value))
+    }
+
+    /// Formats a key/value pair, where the value may not exist.
+    fn key_maybe_value(key: &[u8], value: Option<&[u8]>) -> String {
+        let fmtkey = Self::key(key);
+        let fmtvalue = value.map_or("None".to_string(), |v| Self::value(key, v));
+        format!("{fmtkey} → {fmtvalue}")
+    }
+}
+
+/// Formats raw byte slices without any decoding.
+pub struct Raw;
+
+impl Raw {
+    /// Formats raw bytes as escaped ASCII strings.
+    pub fn bytes(bytes: &[u8]) -> String {
+        let escaped: Vec<u8> = bytes
+            .iter()
+            .copied()
+            .flat_map(std::ascii::escape_default)
+            .collect();
+        format!("\"{}\"", String::from_utf8_lossy(&escaped))
+    }
+}
+
+impl Formatter for Raw {
+    fn key(key: &[u8]) -> String {
+        Self::bytes(key)
+    }
+
+    fn value(_key: &[u8], value: &[u8]) -> String {
+        Self::bytes(value)
+    }
+}
+
+/// Formats SQL keys/values.
+pub struct SQL;
+
+impl SQL {
+    /// Formats a list of SQL values.
+    fn values(values: impl IntoIterator<Item = Value>) -> String {
+        values
+            .into_iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Formats a table schema as a single line.
+    fn schema(table: sql::types::Table) -> String {
+        table
+            .to_string()
+            .lines()
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl Formatter for SQL {
+    fn key(key: &[u8]) -> String {
+        let Ok(key) = sql::engine::Key::decode(key) else {
+            return Raw::key(key);
+        };
+        match key {
+            sql::engine::Key::Table(name) => format!("sql:Table({name:?})"),
+            sql::engine::Key::Index(table, index, values) => {
+                format!(
+                    "sql:Index({table:?}, {index:?}, {})",
+                    Self::values(values.into_owned())
+                )
+            }
+            sql::engine::Key::Row(table, id) => {
+                format!("sql:Row({table:?}, {})", Self::values(id.into_owned()))
+            }
+        }
+    }
+
+    fn value(key: &[u8], value: &[u8]) -> String {
+        let Ok(key) = sql::engine::Key::decode(key) else {
+            return Raw::value(key, value);
+        };
+        match key {
+            sql::engine::Key::Table(_) => {
+                let Ok(table) = bincode::deserialize(value) else {
+                    return Raw::bytes(value);
+                };
+                Self::schema(table)
+            }
+            sql::engine::Key::Index(_, _, _) => {
+                let Ok(ids) = bincode::deserialize::<BTreeSet<Vec<Value>>>(value) else {
+                    return Raw::bytes(value);
+                };
+                let ids: Vec<_> = ids.iter().map(|id| Value::format_tuple(id)).collect();
+                format!("{{{}}}", ids.join(", "))
+            }
+            sql::engine::Key::Row(_, _) => {
+                let Ok(row) = bincode::deserialize::<sql::types::Row>(value) else {
+                    return Raw::bytes(value);
+                };
+                format!("[{}]", Self::values(row))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::Value as _;
+
+    #[test]
+    fn raw() {
+        assert_eq!(Raw::key(b"foo"), r#""foo""#);
+        assert_eq!(Raw::key(&[0x00, 0x7f, 0xff]), r#""\x00\x7f\xff""#);
+        assert_eq!(Raw::key(b"a\"b\n"), r#""a\"b\n""#);
+        assert_eq!(Raw::key_value(b"foo", b"bar"), r#""foo" → "bar""#);
+        assert_eq!(Raw::key_maybe_value(b"foo", None), r#""foo" → None"#);
+    }
+
+    #[test]
+    fn sql() {
+        let key = sql::engine::Key::Row("users".into(), vec![Value::Integer(1)].into()).encode();
+        let row = vec![Value::Integer(1), Value::String("alice".into())].encode();
+        assert_eq!(
+            SQL::key_value(&key, &row),
+            r#"sql:Row("users", 1) → [1, 'alice']"#
+        );
+
+        let key = sql::engine::Key::Index(
+            "users".into(),
+            "name".into(),
+            vec![Value::String("alice".into())].into(),
+        )
+        .encode();
+        let ids = BTreeSet::from([vec![Value::Integer(1)], vec![Value::Integer(3)]]).encode();
+        assert_eq!(
+            SQL::key_value(&key, &ids),
+            r#"sql:Index("users", "name", 'alice') → {1, 3}"#
+        );
+
+        let key = sql::engine::Key::Table("users".into()).encode();
+        let table = sql::types::Table {
+            name: "users".into(),
+            version: 0,
+            primary_key: vec![0],
+            columns: vec![sql::types::Column {
+                name: "id".into(),
+                datatype: sql::types::DataType::Integer,
+                nullable: false,
+                default: None,
+                unique: true,
+                index: false,
+                references: None,
+                on_delete: sql::types::ReferenceAction::Restrict,
+                on_update: sql::types::ReferenceAction::Restrict,
+            }],
+            indexes: Vec::new(),
+        };
+        assert_eq!(
+            SQL::key_value(&key, &table.encode()),
+            r#"sql:Table("users") → CREATE TABLE users ( id INTEGER PRIMARY KEY )"#
+        );
+
+        // Composite primary keys and multi-column indexes use value tuples.
+        let id = vec![Value::Integer(1), Value::String("a".into())];
+        let key = sql::engine::Key::Row("pairs".into(), id.clone().into()).encode();
+        let row = id.encode();
+        assert_eq!(
+            SQL::key_value(&key, &row),
+            r#"sql:Row("pairs", 1, 'a') → [1, 'a']"#
+        );
+
+        let key = sql::engine::Key::Index(
+            "pairs".into(),
+            "pairs_b_a".into(),
+            vec![Value::String("a".into()), Value::Integer(1)].into(),
+        )
+        .encode();
+        let ids = BTreeSet::from([id]).encode();
+        assert_eq!(
+            SQL::key_value(&key, &ids),
+            r#"sql:Index("pairs", "pairs_b_a", 'a', 1) → {(1, 'a')}"#
+        );
+
+        let key = sql::engine::Key::Table("pairs".into()).encode();
+        let column = |name: &str, datatype| sql::types::Column {
+            name: name.into(),
+            datatype,
+            nullable: false,
+            default: None,
+            unique: false,
+            index: false,
+            references: None,
+            on_delete: sql::types::ReferenceAction::Restrict,
+            on_update: sql::types::ReferenceAction::Restrict,
+        };
+        let table = sql::types::Table {
+            name: "pairs".into(),
+            version: 1,
+            primary_key: vec![0, 1],
+            columns: vec![
+                column("a", sql::types::DataType::Integer),
+                column("b", sql::types::DataType::String),
+            ],
+            indexes: vec![sql::types::Index {
+                name: "pairs_b_a".into(),
+                columns: vec!["b".into(), "a".into()],
+                unique: true,
+                ready: true,
+            }],
+        };
+        assert_eq!(
+            SQL::key_value(&key, &table.encode()),
+            "sql:Table(\"pairs\") → CREATE TABLE pairs ( a INTEGER NOT NULL, b STRING NOT NULL, \
+             PRIMARY KEY (a, b) ); CREATE UNIQUE INDEX pairs_b_a ON pairs (b, a)"
+        );
+
+        // A leading prefix of a value tuple encodes to a prefix of the key.
+        let prefix = sql::engine::Key::Row("pairs".into(), vec![Value::Integer(1)].into());
+        let row_key = sql::engine::Key::Row("pairs".into(), vec![1.into(), "a".into()].into());
+        assert!(row_key.encode().starts_with(&prefix.encode()));
+
+        // Undecodable keys and values fall back to raw formatting.
+        assert_eq!(SQL::key_value(b"\xff", b"foo"), r#""\xff" → "foo""#);
+        assert_eq!(SQL::value(&key, b"\xff"), r#""\xff""#);
+    }
+}
@@ -1 +1,749 @@
 #![allow(clippy::module_inception)]
+
+use crate::errinput;
+use crate::error::{Error, Result};
+use crate::sql::types::{Expression, ReferenceAction, Row, Rows, SchemaChange, Table, Value};
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ops::Bound;
+use std::time::Duration;
+
+/// The number of rows backfilled per transaction when building an index.
+const BACKFILL_BATCH_SIZE: usize = 1000;
+/// The number of times a conflicting backfill batch is retried.
+pub const BACKFILL_MAX_RETRIES: u32 = 8;
+/// The initial backoff before retrying a backfill batch, doubled per retry.
+const BACKFILL_RETRY_BACKOFF: Duration = Duration::from_millis(10);
+
+/// A SQL engine. This provides low-level CRUD (create, read, update, delete)
+/// operations for table rows, and a schema catalog for accessing and modifying
+/// table schemas. All engine access is transactional with snapshot isolation.
+pub trait Engine<'a>: Sized {
+    /// The engine's transaction type. This provides both row-level CRUD
+    /// operations and transactional access to the schema catalog. It can't
+    /// outlive the engine.
+    type Transaction: Transaction + Catalog + 'a;
+
+    /// Begins a read-write transaction.
+    fn begin(&'a self) -> Result<Self::Transaction>;
+    /// Begins a read-only transaction.
+    fn begin_read_only(&'a self) -> Result<Self::Transaction>;
+    /// Begins a read-only transaction as of a historical version.
+    fn begin_as_of(&'a self, version: u64) -> Result<Self::Transaction>;
+
+    /// Blocks until all read-write transactions that are active when called
+    /// have finished, i.e. committed or rolled back.
+    fn wait_for_active(&'a self) -> Result<()>;
+
+    /// Backfills a named index added via [`SchemaChange::CreateIndex`], and
+    /// marks it ready for lookups. The index must have been created in a
+    /// committed transaction.
+    ///
+    /// Rows written concurrently maintain the index themselves, but only in
+    /// transactions that see it, so this first waits for transactions that
+    /// began before it was created to finish. Existing rows then have their
+    /// index entries written in primary key order, in batches of separate
+    /// transactions. This avoids a single long-running transaction that would
+    /// conflict with concurrent writers for the entire backfill. A batch that
+    /// conflicts is retried with exponential backoff, up to
+    /// [`BACKFILL_MAX_RETRIES`] times.
+    ///
+    /// For a unique index, each batch checks its values against the index. If
+    /// a value is duplicated, the index is dropped and an error returned. On
+    /// other errors, the index is left as is, and the build can be retried.
+    fn build_index(&'a self, table: &str, index: &str) -> Result<()> {
+        self.wait_for_active()?;
+        let mut after = None;
+        let mut retries = 0;
+        loop {
+            match transact(self, |txn| {
+                backfill_index_batch(txn, table, index, after.clone())
+            }) {
+                Ok(Backfill::Batch(last)) => {
+                    after = Some(last);
+                    retries = 0;
+                }
+                Ok(Backfill::Done) => break,
+                Ok(Backfill::Duplicate(values)) => {
+                    let drop = SchemaChange::DropIndex(index.to_string());
+                    transact(self, |txn| txn.alter_table(table, drop))?;
+                    let values = Value::format_tuple(&values);
+                    return errinput!(
+                        "value {values} already in unique index {index}, dropped index {index}"
+                    );
+                }
+                // Retry the batch on conflicts with concurrent writers.
+                Err(Error::Serialization) if retries < BACKFILL_MAX_RETRIES => {
+                    std::thread::sleep(BACKFILL_RETRY_BACKOFF * 2u32.pow(retries));
+                    retries += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        let ready = SchemaChange::IndexReady(index.to_string());
+        transact(self, |txn| txn.alter_table(table, ready))?;
+        Ok(())
+    }
+}
+
+/// A SQL transaction. Executes transactional access to row data.
+///
+/// All methods operate on normalized table and column names. Rows are
+/// identified by their primary key tuple (see [`Table::row_id`]), which has a
+/// single value unless the table has a composite primary key.
+pub trait Transaction {
+    /// Commits the transaction.
+    fn commit(self) -> Result<()>;
+    /// Rolls back the transaction.
+    fn rollback(self) -> Result<()>;
+
+    /// Deletes table rows by primary key, if they exist. Applies the ON DELETE
+    /// actions of foreign keys referencing the rows, see [`check_references`]
+    /// and [`cascade_references`].
+    fn delete(&self, table: &str, ids: &[Vec<Value>]) -> Result<()>;
+    /// Fetches table rows by primary key, if they exist.
+    fn get(&self, table: &str, ids: &[Vec<Value>]) -> Result<Vec<Row>>;
+    /// Inserts new table rows. Rows are validated with
+    /// [`Table::validate_row`].
+    fn insert(&self, table: &str, rows: Vec<Row>) -> Result<()>;
+    /// Looks up a set of primary keys by index values. BTreeSet for testing.
+    /// Takes a column name for column indexes, or an index name. Each value
+    /// tuple can be a leading prefix of a multi-column index's columns, which
+    /// matches all index entries with that prefix.
+    fn lookup_index(
+        &self,
+        table: &str,
+        index: &str,
+        values: &[Vec<Value>],
+    ) -> Result<BTreeSet<Vec<Value>>>;
+    /// Scans a table's rows in primary key order, optionally applying the
+    /// given filter.
+    fn scan(&self, table: &str, filter: Option<Expression>) -> Result<Rows>;
+    /// Scans a table's rows with primary keys in the given range, in primary
+    /// key order.
+    fn scan_range(
+        &self,
+        table: &str,
+        range: (Bound<Vec<Value>>, Bound<Vec<Value>>),
+    ) -> Result<Rows>;
+    /// Updates table rows by their current primary key. Uses a BTreeMap for
+    /// test determinism. Rows are validated with [`Table::validate_row`],
+    /// passing the current primary key as the old key, and all of their index
+    /// entries are rewritten, including for indexes that aren't ready yet. If a
+    /// row's primary key changes, applies the ON UPDATE actions of foreign keys
+    /// referencing it.
+    fn update(&self, table: &str, rows: BTreeMap<Vec<Value>, Row>) -> Result<()>;
+    /// Writes the given rows' entries for a named index, without validating
+    /// the rows or writing any other data. Used to backfill indexes, see
+    /// [`Engine::build_index`].
+    fn write_index(&self, table: &str, index: &str, rows: &[Row]) -> Result<()>;
+}
+
+/// The catalog stores table schema information. It must be implemented for
+/// Engine::Transaction, and is thus fully transactional. It supports creating,
+/// altering and dropping tables, as well as named indexes.
+///
+/// This type is separate from Transaction, even though Engine::Transaction
+/// requires transactions to implement it. This allows better control of when
+/// catalog access should be used (i.e. during planning, not execution).
+pub trait Catalog {
+    /// Creates a new table. Errors if it already exists, or if the schema is
+    /// invalid (see [`Table::validate`]).
+    fn create_table(&self, table: Table) -> Result<()>;
+    /// Drops a table. Errors if it does not exist (unless if_exists is true),
+    /// or if it is referenced by a different table's foreign key, see
+    /// [`check_drop_table`]. Returns true if the table existed and was
+    /// deleted.
+    fn drop_table(&self, table: &str, if_exists: bool) -> Result<bool>;
+    /// Alters a table schema, and returns the new schema. The schema version is
+    /// incremented (see [`Table::alter`]), and the new schema is validated with
+    /// [`Table::validate_alter`]. Existing rows and index entries are rewritten
+    /// eagerly within the transaction, using the added column's default value
+    /// for existing rows, and the rewritten rows are validated with
+    /// [`Table::validate_row`]. Renaming a table also updates foreign key
+    /// references to it in other tables, incrementing their schema versions.
+    /// Index changes don't rewrite rows: created indexes are backfilled by
+    /// [`Engine::build_index`], and dropped index entries are removed.
+    fn alter_table(&self, table: &str, change: SchemaChange) -> Result<Table>;
+    /// Fetches a table schema, or None if it doesn't exist.
+    fn get_table(&self, table: &str) -> Result<Option<Table>>;
+    /// Returns a list of all table schemas.
+    fn list_tables(&self) -> Result<Vec<Table>>;
+
+    /// Fetches a table schema, or errors if it does not exist.
+    fn must_get_table(&self, table: &str) -> Result<Table> {
+        self.get_table(table)?
+            .ok_or_else(|| errinput!("table {table} does not exist"))
+    }
+
+    /// Fetches the schema of the table with the given named index, if any.
+    fn get_index_table(&self, index: &str) -> Result<Option<Table>> {
+        Ok(self
+            .list_tables()?
+            .into_iter()
+            .find(|t| t.index(index).is_some()))
+    }
+
+    /// Returns all foreign key references to the given table, as source
+    /// tables and their referencing column indexes. Includes self-references.
+    fn table_references(&self, table: &str) -> Result<Vec<(Table, Vec<usize>)>> {
+        Ok(self
+            .list_tables()?
+            .into_iter()
+            .filter_map(|source| {
+                let columns: Vec<usize> = source
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| c.references.as_deref() == Some(table))
+                    .map(|(i, _)| i)
+                    .collect();
+                (!columns.is_empty()).then_some((source, columns))
+            })
+            .collect())
+    }
+}
+
+/// Checks that a table can be dropped, i.e. that it isn't referenced by a
+/// different table's foreign key. Self-references don't prevent dropping.
+///
+/// Catalog implementations must call this before dropping the table.
+pub fn check_drop_table(txn: &impl Catalog, table: &str) -> Result<()> {
+    for (source, columns) in txn.table_references(table)? {
+        if source.name != table {
+            let column = &source.columns[columns[0]];
+            return errinput!(
+                "table {table} is referenced by {}.{}",
+                source.name,
+                column.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Checks that deleting or changing the primary keys of the given table rows
+/// does not violate a RESTRICT foreign key reference. The changes map old
+/// primary keys to new rows, or None if the row is deleted. References from
+/// rows in the same table are ignored if the change deletes the referencing
+/// row, or updates it to no longer hold the reference.
+///
+/// Transaction implementations must call this before deleting or updating the
+/// rows, and [`cascade_references`] afterwards.
+pub fn check_references(
+    txn: &(impl Transaction + Catalog),
+    table: &str,
+    changes: &BTreeMap<Vec<Value>, Option<Row>>,
+) -> Result<()> {
+    let schema = txn.must_get_table(table)?;
+    for (source, columns) in txn.table_references(table)? {
+        for c in columns {
+            let column = &source.columns[c];
+            for (id, new_row) in changes {
+                let new_id = new_row.as_ref().map(|row| schema.row_id(row));
+                let action = match new_id {
+                    None => column.on_delete,
+                    Some(new_id) if &new_id != id => column.on_update,
+                    Some(_) => continue,
+                };
+                if action != ReferenceAction::Restrict {
+                    continue;
+                }
+                let source_ids =
+                    txn.lookup_index(&source.name, &column.name, std::slice::from_ref(id))?;
+                let source_id = source_ids.into_iter().find(|source_id| {
+                    if source.name != table {
+                        return true;
+                    }
+                    match changes.get(source_id) {
+                        None => true,
+                        Some(None) => false,
+                        Some(Some(row)) => std::slice::from_ref(&row[c]) == id.as_slice(),
+                    }
+                });
+                if let Some(source_id) = source_id {
+                    return errinput!(
+                        "row {} in table {table} is referenced by {}.{} in row {}",
+                        Value::format_tuple(id),
+                        source.name,
+                        column.name,
+                        Value::format_tuple(&source_id)
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Applies the CASCADE and SET NULL foreign key actions to rows referencing
+/// the given, deleted or changed, table rows. The changes map old primary keys
+/// to new rows, or None if the row was deleted.
+///
+/// Transaction implementations must call this after deleting or updating the
+/// rows, such that cascades terminate on reference cycles.
+pub fn cascade_references(
+    txn: &(impl Transaction + Catalog),
+    table: &str,
+    changes: &BTreeMap<Vec<Value>, Option<Row>>,
+) -> Result<()> {
+    let schema = txn.must_get_table(table)?;
+    for (source, columns) in txn.table_references(table)? {
+        for index in columns {
+            let column = &source.columns[index];
+            let mut delete = Vec::new();
+            let mut update = BTreeMap::new();
+            for (id, new_row) in changes {
+                let new_id = new_row.as_ref().map(|row| schema.row_id(row));
+                let action = match &new_id {
+                    None => column.on_delete,
+                    Some(new_id) if new_id != id => column.on_update,
+                    Some(_) => continue,
+                };
+                if action == ReferenceAction::Restrict {
+                    continue;
+                }
+                let source_ids =
+                    txn.lookup_index(&source.name, &column.name, std::slice::from_ref(id))?;
+                let source_ids: Vec<_> = source_ids.into_iter().collect();
+                for mut row in txn.get(&source.name, &source_ids)? {
+                    let source_id = source.row_id(&row);
+                    match (action, &new_id) {
+                        (ReferenceAction::Cascade, None) => delete.push(source_id),
+                        (ReferenceAction::Cascade, Some(new_id)) => {
+                            // Referenced primary keys are a single column.
+                            row[index] = new_id[0].clone();
+                            update.insert(source_id, row);
+                        }
+                        (ReferenceAction::SetNull, _) => {
+                            row[index] = Value::Null;
+                            update.insert(source_id, row);
+                        }
+                        (ReferenceAction::Restrict, _) => {}
+                    }
+                }
+            }
+            if !delete.is_empty() {
+                txn.delete(&source.name, &delete)?;
+            }
+            if !update.is_empty() {
+                txn.update(&source.name, update)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The outcome of backfilling a batch of index entries.
+enum Backfill {
+    /// A batch was written, up to and including the given primary key.
+    Batch(Vec<Value>),
+    /// There are no more rows to backfill.
+    Done,
+    /// The given value tuple is already in the unique index.
+    Duplicate(Vec<Value>),
+}
+
+/// Writes the index entries of a batch of table rows after the given primary
+/// key. For a unique index, first checks the batch's values against the index
+/// entries written so far, and against each other (ignoring NULL and NaN).
+fn backfill_index_batch(
+    txn: &(impl Transaction + Catalog),
+    table: &str,
+    index: &str,
+    after: Option<Vec<Value>>,
+) -> Result<Backfill> {
+    let schema = txn.must_get_table(table)?;
+    let Some(index) = schema.index(index) else {
+        return errinput!("index {index} does not exist in table {table}");
+    };
+    let range = (
+        after.map_or(Bound::Unbounded, Bound::Excluded),
+        Bound::Unbounded,
+    );
+    let rows: Vec<Row> = txn
+        .scan_range(table, range)?
+        .take(BACKFILL_BATCH_SIZE)
+        .collect::<Result<_>>()?;
+    let Some(last) = rows.last().map(|row| schema.row_id(row)) else {
+        return Ok(Backfill::Done);
+    };
+
+    if index.unique {
+        let mut seen = HashSet::new();
+        for row in &rows {
+            let values = schema.index_values(index, row)?;
+            if values.iter().any(|v| v.is_undefined()) {
+                continue;
+            }
+            let mut ids = txn.lookup_index(table, &index.name, std::slice::from_ref(&values))?;
+            ids.remove(&schema.row_id(row));
+            if !ids.is_empty() || !seen.insert(values.clone()) {
+                return Ok(Backfill::Duplicate(values));
+            }
+        }
+    }
+
+    txn.write_index(table, &index.name, &rows)?;
+    Ok(Backfill::Batch(last))
+}
+
+/// Runs a closure in a new read-write transaction, committing it if the closure
+/// succeeds and rolling it back otherwise.
+fn transact<'a, E: Engine<'a>, T>(
+    engine: &'a E,
+    f: impl FnOnce(&E::Transaction) -> Result<T>,
+) -> Result<T> {
+    let txn = engine.begin()?;
+    match f(&txn) {
+        Ok(result) => txn.commit().map(|_| result),
+        Err(err) => txn.rollback().and(Err(err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::sql::engine::test::{TestEngine, TestTransaction};
+    use crate::sql::types::{Column, DataType, Index};
+
+    /// CREATE TABLE employees (id INTEGER PRIMARY KEY, manager INTEGER NULL
+    /// DEFAULT NULL INDEX REFERENCES employees ON DELETE $on_delete ON UPDATE
+    /// $on_update), with employee 1 managed by employee 2.
+    fn employees(
+        on_delete: ReferenceAction,
+        on_update: ReferenceAction,
+    ) -> Result<TestTransaction> {
+        let id = Column {
+            name: "id".into(),
+            datatype: DataType::Integer,
+            nullable: false,
+            default: None,
+            unique: true,
+            index: false,
+            references: None,
+            on_delete: ReferenceAction::Restrict,
+            on_update: ReferenceAction::Restrict,
+        };
+        let manager = Column {
+            name: "manager".into(),
+            nullable: true,
+            default: Some(Value::Null),
+            unique: false,
+            index: true,
+            references: Some("employees".into()),
+            on_delete,
+            on_update,
+            ..id.clone()
+        };
+        let table = Table {
+            name: "employees".into(),
+            version: 0,
+            primary_key: vec![0],
+            columns: vec![id, manager],
+            indexes: Vec::new(),
+        };
+        let txn = TestTransaction::with_tables([table])?;
+        txn.insert(
+            "employees",
+            vec![vec![2.into(), Value::Null], vec![1.into(), 2.into()]],
+        )?;
+        Ok(txn)
+    }
+
+    /// Returns all employees as (id, manager) rows.
+    fn scan_employees(txn: &TestTransaction) -> Result<Vec<Row>> {
+        txn.scan("employees", None)?.collect()
+    }
+
+    /// Changing a row's primary key errors if a same-table row references it,
+    /// unless the referencing row is deleted or re-pointed in the same batch.
+    #[test]
+    fn check_references_same_table() -> Result<()> {
+        use ReferenceAction::Restrict;
+        let txn = employees(Restrict, Restrict)?;
+        let row = |id: i64, manager: Value| Some(vec![id.into(), manager]);
+        let error = Err(Error::InvalidInput(
+            "row 2 in table employees is referenced by employees.manager in row 1".into(),
+        ));
+
+        // Row 1 is unchanged, or only updated, so it still references row 2.
+        let changes = BTreeMap::from([(vec![2.into()], row(3, Value::Null))]);
+        assert_eq!(check_references(&txn, "employees", &changes), error);
+        let changes = BTreeMap::from([
+            (vec![1.into()], row(1, 2.into())),
+            (vec![2.into()], row(3, Value::Null)),
+        ]);
+        assert_eq!(check_references(&txn, "employees", &changes), error);
+        let changes = BTreeMap::from([(vec![2.into()], None)]);
+        assert_eq!(check_references(&txn, "employees", &changes), error);
+
+        // Row 1 is deleted, or re-pointed to row 3.
+        let changes = BTreeMap::from([
+            (vec![1.into()], None),
+            (vec![2.into()], row(3, Value::Null)),
+        ]);
+        assert_eq!(check_references(&txn, "employees", &changes), Ok(()));
+        let changes = BTreeMap::from([
+            (vec![1.into()], row(1, 3.into())),
+            (vec![2.into()], row(3, Value::Null)),
+        ]);
+        assert_eq!(check_references(&txn, "employees", &changes), Ok(()));
+
+        // Row 2 keeps its primary key.
+        let changes = BTreeMap::from([(vec![2.into()], row(2, 1.into()))]);
+        assert_eq!(check_references(&txn, "employees", &changes), Ok(()));
+        Ok(())
+    }
+
+    #[test]
+    fn cascade_delete() -> Result<()> {
+        use ReferenceAction::{Cascade, Restrict};
+        let txn = employees(Cascade, Restrict)?;
+        txn.insert(
+            "employees",
+            vec![vec![3.into(), 1.into()], vec![4.into(), Value::Null]],
+        )?;
+
+        // Deleting 2 deletes 1, which deletes 3.
+        txn.delete("employees", &[vec![2.into()]])?;
+        assert_eq!(scan_employees(&txn)?, vec![vec![4.into(), Value::Null]]);
+        Ok(())
+    }
+
+    #[test]
+    fn cascade_delete_cycle() -> Result<()> {
+        use ReferenceAction::{Cascade, Restrict};
+        let txn = employees(Cascade, Restrict)?;
+        txn.update(
+            "employees",
+            BTreeMap::from([(vec![2.into()], vec![2.into(), 1.into()])]),
+        )?;
+
+        // Cascades terminate, since deleted rows are no longer referencing.
+        txn.delete("employees", &[vec![1.into()]])?;
+        assert_eq!(scan_employees(&txn)?, Vec::<Row>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn cascade_update() -> Result<()> {
+        use ReferenceAction::{Cascade, Restrict};
+        let txn = employees(Restrict, Cascade)?;
+        txn.update(
+            "employees",
+            BTreeMap::from([(vec![2.into()], vec![3.into(), Value::Null])]),
+        )?;
+        assert_eq!(
+            scan_employees(&txn)?,
+            vec![vec![1.into(), 3.into()], vec![3.into(), Value::Null]]
+        );
+
+        // Updating a row without changing its primary key doesn't cascade.
+        txn.update(
+            "employees",
+            BTreeMap::from([(vec![3.into()], vec![3.into(), 1.into()])]),
+        )?;
+        assert_eq!(
+            scan_employees(&txn)?,
+            vec![vec![1.into(), 3.into()], vec![3.into(), 1.into()]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn set_null() -> Result<()> {
+        use ReferenceAction::SetNull;
+        let txn = employees(SetNull, SetNull)?;
+        txn.insert(
+            "employees",
+            vec![vec![3.into(), 2.into()], vec![4.into(), 3.into()]],
+        )?;
+
+        // Changing 3's key nulls 4's reference, deleting 2 nulls 1's.
+        txn.update(
+            "employees",
+            BTreeMap::from([(vec![3.into()], vec![5.into(), 2.into()])]),
+        )?;
+        txn.delete("employees", &[vec![2.into()]])?;
+        assert_eq!(
+            scan_employees(&txn)?,
+            vec![
+                vec![1.into(), Value::Null],
+                vec![4.into(), Value::Null],
+                vec![5.into(), Value::Null],
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn restrict() -> Result<()> {
+        use ReferenceAction::Restrict;
+        let txn = employees(Restrict, Restrict)?;
+        let error = Err(Error::InvalidInput(
+            "row 2 in table employees is referenced by employees.manager in row 1".into(),
+        ));
+        assert_eq!(txn.delete("employees", &[vec![2.into()]]), error);
+        let rows = BTreeMap::from([(vec![2.into()], vec![3.into(), Value::Null])]);
+        assert_eq!(txn.update("employees", rows), error);
+
+        // Deleting both rows together is fine.
+        txn.delete("employees", &[vec![1.into()], vec![2.into()]])?;
+        assert_eq!(scan_employees(&txn)?, Vec::<Row>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn drop_table() -> Result<()> {
+        use ReferenceAction::Restrict;
+        let txn = employees(Restrict, Restrict)?;
+        let mut projects = txn.must_get_table("employees")?;
+        projects.name = "projects".into();
+        projects.columns[1].name = "owner".into();
+        txn.create_table(projects)?;
+
+        // employees is referenced by projects, but only by itself once
+        // projects is dropped.
+        assert_eq!(
+            txn.drop_table("employees", false),
+            Err(Error::InvalidInput(
+                "table employees is referenced by projects.owner".into()
+            ))
+        );
+        assert!(txn.drop_table("projects", false)?);
+        assert!(txn.drop_table("employees", false)?);
+        assert!(!txn.drop_table("employees", true)?);
+        Ok(())
+    }
+
+    /// CREATE TABLE badges (id INTEGER PRIMARY KEY, badge INTEGER NULL DEFAULT
+    /// NULL) with the given rows, and CREATE [UNIQUE] INDEX badges_badge ON
+    /// badges ($columns), without building the index.
+    fn badges(rows: Vec<Row>, columns: &[&str], unique: bool) -> Result<TestEngine> {
+        let id = Column {
+            name: "id".into(),
+            datatype: DataType::Integer,
+            nullable: false,
+            default: None,
+            unique: true,
+            index: false,
+            references: None,
+            on_delete: ReferenceAction::Restrict,
+            on_update: ReferenceAction::Restrict,
+        };
+        let badge = Column {
+            name: "badge".into(),
+            nullable: true,
+            default: Some(Value::Null),
+            unique: false,
+            ..id.clone()
+        };
+        let table = Table {
+            name: "badges".into(),
+            version: 0,
+            primary_key: vec![0],
+            columns: vec![id, badge],
+            indexes: Vec::new(),
+        };
+        let txn = TestTransaction::with_tables([table])?;
+        txn.insert("badges", rows)?;
+        let index = Index {
+            name: "badges_badge".into(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            unique,
+            ready: false,
+        };
+        txn.alter_table("badges", SchemaChange::CreateIndex(index))?;
+        Ok(TestEngine::new(txn))
+    }
+
+    /// Builds an index over several batches, including rows written after it
+    /// was created.
+    #[test]
+    fn build_index() -> Result<()> {
+        let count = 2 * BACKFILL_BATCH_SIZE as i64 + 10;
+        let rows = (0..count).map(|i| vec![i.into(), i.into()]).collect();
+        let engine = badges(rows, &["badge"], true)?;
+        let txn = engine.begin()?;
+        txn.insert("badges", vec![vec![count.into(), Value::Null]])?;
+        txn.insert("badges", vec![vec![(count + 1).into(), (-1).into()]])?;
+
+        engine.build_index("badges", "badges_badge")?;
+        let table = txn.must_get_table("badges")?;
+        assert_eq!(table.index("badges_badge").map(|i| i.ready), Some(true));
+        for value in [0, BACKFILL_BATCH_SIZE as i64, count - 1, -1] {
+            let ids = txn.lookup_index("badges", "badges_badge", &[vec![value.into()]])?;
+            assert_eq!(ids.len(), 1, "value {value}");
+        }
+        let ids = txn.lookup_index("badges", "badges_badge", &[vec![Value::Null]])?;
+        assert_eq!(ids, BTreeSet::from([vec![count.into()]]));
+        Ok(())
+    }
+
+    /// Non-unique indexes allow duplicate values.
+    #[test]
+    fn build_index_duplicates() -> Result<()> {
+        let rows = (0..5).map(|i| vec![i.into(), (i % 2).into()]).collect();
+        let engine = badges(rows, &["badge"], false)?;
+        engine.build_index("badges", "badges_badge")?;
+        let ids = engine
+            .begin()?
+            .lookup_index("badges", "badges_badge", &[vec![1.into()]])?;
+        assert_eq!(ids, BTreeSet::from([vec![1.into()], vec![3.into()]]));
+        Ok(())
+    }
+
+    /// Multi-column indexes can be looked up by a prefix of their columns.
+    #[test]
+    fn build_index_multiple_columns() -> Result<()> {
+        let rows = (0..5).map(|i| vec![i.into(), (i % 2).into()]).collect();
+        let engine = badges(rows, &["badge", "id"], true)?;
+        engine.build_index("badges", "badges_badge")?;
+        let txn = engine.begin()?;
+        let ids = txn.lookup_index("badges", "badges_badge", &[vec![1.into()]])?;
+        assert_eq!(ids, BTreeSet::from([vec![1.into()], vec![3.into()]]));
+        let ids = txn.lookup_index("badges", "badges_badge", &[vec![1.into(), 3.into()]])?;
+        assert_eq!(ids, BTreeSet::from([vec![3.into()]]));
+        Ok(())
+    }
+
+    /// Duplicate values drop a unique index, both within a batch and across
+    /// batches.
+    #[test]
+    fn build_index_unique_duplicate() -> Result<()> {
+        let last = BACKFILL_BATCH_SIZE as i64 + 5;
+        for rows in [
+            vec![vec![1.into(), 7.into()], vec![2.into(), 7.into()]],
+            vec![vec![1.into(), 7.into()], vec![last.into(), 7.into()]],
+        ] {
+            let engine = badges(rows, &["badge"], true)?;
+            assert_eq!(
+                engine.build_index("badges", "badges_badge"),
+                Err(Error::InvalidInput(
+                    "value 7 already in unique index badges_badge, dropped index badges_badge"
+                        .into()
+                ))
+            );
+            let table = engine.begin()?.must_get_table("badges")?;
+            assert_eq!(table.index("badges_badge"), None);
+        }
+        Ok(())
+    }
+
+    /// Other errors are returned as is, and don't drop indexes.
+    #[test]
+    fn build_index_error() -> Result<()> {
+        let engine = badges(vec![vec![1.into(), 7.into()]], &["badge"], true)?;
+        assert_eq!(
+            engine.build_index("badges", "foo"),
+            Err(Error::InvalidInput(
+                "index foo does not exist in table badges".into()
+            ))
+        );
+        let table = engine.begin()?.must_get_table("badges")?;
+        assert_eq!(table.index("badges_badge").map(|i| i.ready), Some(false));
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use crate::encoding;
+use crate::sql::types::Value;
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// SQL engine keys, using the Keycode order-preserving encoding. For
+/// simplicity, table and column names are used directly as identifiers in
+/// keys, instead of e.g. numeric IDs. This means that renaming a table or an
+/// indexed column must rewrite all of its keys, but this is rare enough.
+///
+/// Table schemas and index entries are stored with Bincode-encoded values of
+/// [`crate::sql::types::Table`] and `BTreeSet<Vec<Value>>` (primary keys)
+/// respectively. Rows are Bincode-encoded [`crate::sql::types::Row`]s.
+///
+/// Primary keys and index values are value tuples, to support composite
+/// primary keys and multi-column indexes. Keycode encodes a tuple as the plain
+/// concatenation of its values, which are self-delimiting, so a leading prefix
+/// of the tuple encodes to a prefix of the key and can be found with a prefix
+/// scan. This requires the tuple to be the last key field.
+///
+/// Primary key and index values must be normalized (see
+/// [`Value::normalize`]), such that e.g. -0.0 and 0.0 map to the same key.
+///
+/// Uses Cow to allow encoding borrowed values but decoding owned values.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum Key<'a> {
+    /// A table schema by table name.
+    Table(Cow<'a, str>),
+    /// An index entry, by table name, column or index name, and index values.
+    Index(Cow<'a, str>, Cow<'a, str>, Cow<'a, [Value]>),
+    /// A table row, by table name and primary key values.
+    Row(Cow<'a, str>, Cow<'a, [Value]>),
+}
+
+impl<'a> encoding::Key<'a> for Key<'a> {}
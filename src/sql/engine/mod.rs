@@ -11,3 +11,10 @@ mod engine;
 mod local;
 mod raft;
 mod session;
+#[cfg(test)]
+pub(crate) mod test;
+
+pub use engine::{
+    cascade_references, check_drop_table, check_references, Catalog, Engine, Transaction,
+};
+pub use local::Key;
@@ -0,0 +1,332 @@
+//! An in-memory engine and transaction for tests, implementing [`Engine`],
+//! [`Transaction`] and [`Catalog`] with plain maps. It isn't transactional,
+//! but validates schemas and rows, maintains named indexes and enforces
+//! foreign keys like a real engine.
+
+use super::{cascade_references, check_drop_table, check_references, Catalog, Engine, Transaction};
+use crate::errinput;
+use crate::error::Result;
+use crate::sql::types::{Expression, Row, Rows, SchemaChange, Table, Value};
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
+use std::rc::Rc;
+
+/// Table rows by primary key.
+type TableRows = BTreeMap<Vec<Value>, Row>;
+/// Named index entries, as primary keys by index value tuple.
+type IndexEntries = BTreeMap<Vec<Value>, BTreeSet<Vec<Value>>>;
+
+/// An in-memory test engine. All transactions share the same state, and
+/// writes are visible immediately.
+pub struct TestEngine {
+    txn: TestTransaction,
+}
+
+impl TestEngine {
+    /// Creates a test engine using the given transaction's state.
+    pub fn new(txn: TestTransaction) -> Self {
+        Self { txn }
+    }
+}
+
+impl<'a> Engine<'a> for TestEngine {
+    type Transaction = TestTransaction;
+
+    fn begin(&'a self) -> Result<Self::Transaction> {
+        Ok(self.txn.clone())
+    }
+
+    fn begin_read_only(&'a self) -> Result<Self::Transaction> {
+        Ok(self.txn.clone())
+    }
+
+    fn begin_as_of(&'a self, _: u64) -> Result<Self::Transaction> {
+        Ok(self.txn.clone())
+    }
+
+    fn wait_for_active(&'a self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// An in-memory test transaction. Clones share the same state.
+#[derive(Clone, Default)]
+pub struct TestTransaction {
+    tables: Rc<RefCell<BTreeMap<String, Table>>>,
+    rows: Rc<RefCell<BTreeMap<String, TableRows>>>,
+    indexes: Rc<RefCell<BTreeMap<String, IndexEntries>>>,
+}
+
+impl TestTransaction {
+    /// Creates a test transaction with the given tables.
+    pub fn with_tables(tables: impl IntoIterator<Item = Table>) -> Result<Self> {
+        let txn = Self::default();
+        for table in tables {
+            txn.create_table(table)?;
+        }
+        Ok(txn)
+    }
+
+    /// Writes a row and its named index entries. Any existing row with the
+    /// same primary key must have been removed first.
+    fn write(&self, table: &Table, row: Row) -> Result<()> {
+        self.write_index_entries(table, &row, true)?;
+        let id = table.row_id(&row);
+        let mut rows = self.rows.borrow_mut();
+        rows.entry(table.name.clone()).or_default().insert(id, row);
+        Ok(())
+    }
+
+    /// Removes a row and its named index entries, if it exists.
+    fn remove(&self, table: &Table, id: &[Value]) -> Result<()> {
+        let row = self
+            .rows
+            .borrow_mut()
+            .get_mut(&table.name)
+            .and_then(|rows| rows.remove(id));
+        if let Some(row) = row {
+            self.write_index_entries(table, &row, false)?;
+        }
+        Ok(())
+    }
+
+    /// Adds or removes a row's entries for all of the table's named indexes.
+    fn write_index_entries(&self, table: &Table, row: &[Value], add: bool) -> Result<()> {
+        for index in &table.indexes {
+            self.write_index_entry(table, &index.name, row, add)?;
+        }
+        Ok(())
+    }
+
+    /// Adds or removes a row's entry for a named index.
+    fn write_index_entry(
+        &self,
+        table: &Table,
+        index: &str,
+        row: &[Value],
+        add: bool,
+    ) -> Result<()> {
+        let Some(index) = table.index(index) else {
+            return errinput!("index {index} does not exist in table {}", table.name);
+        };
+        let value = table.index_values(index, row)?;
+        let id = table.row_id(row);
+        let mut indexes = self.indexes.borrow_mut();
+        let entries = indexes.entry(index.name.clone()).or_default();
+        if add {
+            entries.entry(value).or_default().insert(id);
+        } else if let Some(ids) = entries.get_mut(&value) {
+            ids.remove(&id);
+            if ids.is_empty() {
+                entries.remove(&value);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Transaction for TestTransaction {
+    fn commit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn rollback(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn delete(&self, table: &str, ids: &[Vec<Value>]) -> Result<()> {
+        let schema = self.must_get_table(table)?;
+        let changes = ids.iter().map(|id| (id.clone(), None)).collect();
+        check_references(self, table, &changes)?;
+        for id in ids {
+            self.remove(&schema, id)?;
+        }
+        cascade_references(self, table, &changes)
+    }
+
+    fn get(&self, table: &str, ids: &[Vec<Value>]) -> Result<Vec<Row>> {
+        let rows = self.rows.borrow();
+        let Some(rows) = rows.get(table) else {
+            return Ok(Vec::new());
+        };
+        Ok(ids.iter().filter_map(|id| rows.get(id).cloned()).collect())
+    }
+
+    fn insert(&self, table: &str, rows: Vec<Row>) -> Result<()> {
+        let schema = self.must_get_table(table)?;
+        for row in rows {
+            schema.validate_row(&row, None, self)?;
+            self.write(&schema, row)?;
+        }
+        Ok(())
+    }
+
+    fn lookup_index(
+        &self,
+        table: &str,
+        index: &str,
+        values: &[Vec<Value>],
+    ) -> Result<BTreeSet<Vec<Value>>> {
+        let schema = self.must_get_table(table)?;
+        if schema.index(index).is_some() {
+            let indexes = self.indexes.borrow();
+            return Ok(indexes
+                .get(index)
+                .into_iter()
+                .flatten()
+                .filter(|(key, _)| values.iter().any(|v| key.starts_with(v)))
+                .flat_map(|(_, ids)| ids.iter().cloned())
+                .collect());
+        }
+        let Some(column) = schema.columns.iter().position(|c| c.name == index) else {
+            return errinput!("unknown column {index} in table {table}");
+        };
+        let rows = self.rows.borrow();
+        Ok(rows
+            .get(table)
+            .into_iter()
+            .flat_map(|rows| rows.iter())
+            .filter(|(_, row)| values.iter().any(|v| v[..] == row[column..=column]))
+            .map(|(id, _)| id.clone())
+            .collect())
+    }
+
+    fn scan(&self, table: &str, filter: Option<Expression>) -> Result<Rows> {
+        if filter.is_some() {
+            return errinput!("filters are not supported by the test transaction");
+        }
+        let rows = self.rows.borrow();
+        let rows: Vec<Row> = rows
+            .get(table)
+            .into_iter()
+            .flat_map(|r| r.values().cloned())
+            .collect();
+        Ok(Box::new(rows.into_iter().map(Ok)))
+    }
+
+    fn scan_range(
+        &self,
+        table: &str,
+        range: (Bound<Vec<Value>>, Bound<Vec<Value>>),
+    ) -> Result<Rows> {
+        let rows = self.rows.borrow();
+        let rows: Vec<Row> = rows
+            .get(table)
+            .into_iter()
+            .flat_map(|r| r.range(range.clone()).map(|(_, row)| row.clone()))
+            .collect();
+        Ok(Box::new(rows.into_iter().map(Ok)))
+    }
+
+    fn update(&self, table: &str, rows: BTreeMap<Vec<Value>, Row>) -> Result<()> {
+        let schema = self.must_get_table(table)?;
+        let changes = rows
+            .iter()
+            .map(|(id, row)| (id.clone(), Some(row.clone())))
+            .collect();
+        check_references(self, table, &changes)?;
+        for (id, row) in rows {
+            schema.validate_row(&row, Some(&id), self)?;
+            self.remove(&schema, &id)?;
+            self.write(&schema, row)?;
+        }
+        cascade_references(self, table, &changes)
+    }
+
+    fn write_index(&self, table: &str, index: &str, rows: &[Row]) -> Result<()> {
+        let schema = self.must_get_table(table)?;
+        for row in rows {
+            self.write_index_entry(&schema, index, row, true)?;
+        }
+        Ok(())
+    }
+}
+
+impl Catalog for TestTransaction {
+    fn create_table(&self, table: Table) -> Result<()> {
+        if self.get_table(&table.name)?.is_some() {
+            return errinput!("table {} already exists", table.name);
+        }
+        table.validate(self)?;
+        self.tables.borrow_mut().insert(table.name.clone(), table);
+        Ok(())
+    }
+
+    fn drop_table(&self, table: &str, if_exists: bool) -> Result<bool> {
+        if self.get_table(table)?.is_none() {
+            if !if_exists {
+                return errinput!("table {table} does not exist");
+            }
+            return Ok(false);
+        }
+        check_drop_table(self, table)?;
+        if let Some(schema) = self.tables.borrow_mut().remove(table) {
+            let mut indexes = self.indexes.borrow_mut();
+            schema
+                .indexes
+                .iter()
+                .for_each(|i| _ = indexes.remove(&i.name));
+        }
+        self.rows.borrow_mut().remove(table);
+        Ok(true)
+    }
+
+    fn alter_table(&self, table: &str, change: SchemaChange) -> Result<Table> {
+        let schema = self.must_get_table(table)?;
+        let new = schema.alter(&change)?;
+        new.validate_alter(&schema, &change, self)?;
+
+        match &change {
+            SchemaChange::CreateIndex(index) => {
+                self.indexes
+                    .borrow_mut()
+                    .insert(index.name.clone(), BTreeMap::new());
+            }
+            SchemaChange::DropIndex(index) => _ = self.indexes.borrow_mut().remove(index),
+            _ => {}
+        }
+
+        let rows = self.rows.borrow_mut().remove(table).unwrap_or_default();
+        let rows: TableRows = rows
+            .into_iter()
+            .map(|(id, row)| Ok((id, schema.alter_row(&change, row)?)))
+            .collect::<Result<_>>()?;
+        self.tables.borrow_mut().remove(table);
+        self.tables
+            .borrow_mut()
+            .insert(new.name.clone(), new.clone());
+        self.rows
+            .borrow_mut()
+            .insert(new.name.clone(), rows.clone());
+        for (id, row) in &rows {
+            new.validate_row(row, Some(id), self)?;
+        }
+
+        if let SchemaChange::RenameTable(name) = &change {
+            for mut source in self.list_tables()? {
+                let mut changed = false;
+                for column in &mut source.columns {
+                    if column.references.as_deref() == Some(table) {
+                        column.references = Some(name.clone());
+                        changed = true;
+                    }
+                }
+                if changed {
+                    source.version += 1;
+                    self.tables.borrow_mut().insert(source.name.clone(), source);
+                }
+            }
+        }
+        Ok(new)
+    }
+
+    fn get_table(&self, table: &str) -> Result<Option<Table>> {
+        Ok(self.tables.borrow().get(table).cloned())
+    }
+
+    fn list_tables(&self) -> Result<Vec<Table>> {
+        Ok(self.tables.borrow().values().cloned().collect())
+    }
+}
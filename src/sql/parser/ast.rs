@@ -1,4 +1,4 @@
-use crate::sql::types::DataType;
+use crate::sql::types::{DataType, ReferenceAction};
 
 use std::collections::BTreeMap;
 
@@ -11,16 +11,32 @@ pub enum Expression {
     Column(Option<String>, String),
     /// A literal value.
     Literal(Literal),
-    /// A function call (name and parameters).
+    /// A function call (name and parameters). This includes COALESCE and
+    /// NULLIF, which have no special syntax.
     Function(String, Vec<Expression>),
     /// An operator.
     Operator(Operator),
+    /// A scalar subquery, which must return a single column and at most one
+    /// row (NULL if none). Like other subqueries, it can reference columns of
+    /// the outer query, i.e. be correlated.
+    Subquery(Box<Select>),
+    /// EXISTS (SELECT ...): true if the subquery returns any rows.
+    Exists(Box<Select>),
+    /// A CASE expression. With an operand, this is a simple CASE comparing the
+    /// operand to each WHEN value, otherwise a searched CASE with WHEN
+    /// conditions. Returns NULL if nothing matches and there's no ELSE.
+    Case {
+        operand: Option<Box<Expression>>,
+        when: Vec<(Expression, Expression)>,
+        r#else: Option<Box<Expression>>,
+    },
 }
 
 impl Expression {
     // noinspection DuplicatedCode
     /// Walks the expression tree depth-first, calling a closure for every node.
-    /// Halts and returns false if the closure returns false.
+    /// Halts and returns false if the closure returns false. Does not descend
+    /// into subqueries, which have their own scope.
     pub fn walk(&self, visitor: &mut impl FnMut(&Expression) -> bool) -> bool {
         use Operator::*;
         if !visitor(self) {
@@ -45,13 +61,35 @@ impl Expression {
 
             Self::Operator(Factorial(expr))
             | Self::Operator(Identity(expr))
+            | Self::Operator(InSubquery(expr, _))
             | Self::Operator(Is(expr, _))
             | Self::Operator(Negate(expr))
             | Self::Operator(Not(expr)) => expr.walk(visitor),
 
+            Self::Operator(Between(expr, low, high)) => {
+                expr.walk(visitor) && low.walk(visitor) && high.walk(visitor)
+            }
+            Self::Operator(InList(expr, list)) => {
+                expr.walk(visitor) && list.iter().all(|e| e.walk(visitor))
+            }
+
+            Self::Case {
+                operand,
+                when,
+                r#else,
+            } => {
+                operand.iter().all(|e| e.walk(visitor))
+                    && when.iter().all(|(c, r)| c.walk(visitor) && r.walk(visitor))
+                    && r#else.iter().all(|e| e.walk(visitor))
+            }
+
             Self::Function(_func_name, exprs) => exprs.iter().any(|expr| expr.walk(visitor)),
 
-            Self::All | Self::Column(_, _) | Self::Literal(_) => true,
+            Self::All
+            | Self::Column(_, _)
+            | Self::Literal(_)
+            | Self::Subquery(_)
+            | Self::Exists(_) => true,
         }
     }
 
@@ -63,7 +101,8 @@ impl Expression {
 
     // noinspection DuplicatedCode
     /// Find and collects expressions for which the given closure returns true,
-    /// adding them to c. Does not recurse into matching expressions.
+    /// adding them to c. Does not recurse into matching expressions or
+    /// subqueries.
     pub fn collect(&self, visitor: &impl Fn(&Expression) -> bool, c: &mut Vec<Expression>) {
         if visitor(self) {
             c.push(self.clone());
@@ -92,13 +131,41 @@ impl Expression {
 
             Self::Operator(Factorial(expr))
             | Self::Operator(Identity(expr))
+            | Self::Operator(InSubquery(expr, _))
             | Self::Operator(Is(expr, _))
             | Self::Operator(Negate(expr))
             | Self::Operator(Not(expr)) => expr.collect(visitor, c),
 
+            Self::Operator(Between(expr, low, high)) => {
+                expr.collect(visitor, c);
+                low.collect(visitor, c);
+                high.collect(visitor, c);
+            }
+            Self::Operator(InList(expr, list)) => {
+                expr.collect(visitor, c);
+                list.iter().for_each(|e| e.collect(visitor, c));
+            }
+
+            Self::Case {
+                operand,
+                when,
+                r#else,
+            } => {
+                operand.iter().for_each(|e| e.collect(visitor, c));
+                for (condition, result) in when {
+                    condition.collect(visitor, c);
+                    result.collect(visitor, c);
+                }
+                r#else.iter().for_each(|e| e.collect(visitor, c));
+            }
+
             Self::Function(_, exprs) => exprs.iter().for_each(|expr| expr.collect(visitor, c)),
 
-            Self::All | Self::Column(_, _) | Self::Literal(_) => {}
+            Self::All
+            | Self::Column(_, _)
+            | Self::Literal(_)
+            | Self::Subquery(_)
+            | Self::Exists(_) => {}
         }
     }
 }
@@ -126,6 +193,7 @@ impl core::convert::From<Operator> for Box<Expression> {
 /// the parser, and passed on to the planner which validates it and builds an
 /// execution plan from it.
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Statement {
     /// Begin a new transaction.
     Begin { read_only: bool, as_of: Option<u64> },
@@ -135,10 +203,27 @@ pub enum Statement {
     Rollback,
     /// Explain a statement.
     Explain(Box<Statement>),
-    /// Create a new table.
-    CreateTable { name: String, columns: Vec<Column> },
+    /// Create a new table. A composite primary key is given as a PRIMARY KEY
+    /// (a, b) table constraint in primary_key, otherwise it's empty and a
+    /// single column is marked as primary key.
+    CreateTable {
+        name: String,
+        columns: Vec<Column>,
+        primary_key: Vec<String>,
+    },
     /// Drop a table.
     DropTable { name: String, if_exists: bool },
+    /// Alter a table's schema.
+    AlterTable { name: String, change: SchemaChange },
+    /// Create a named secondary index on one or more table columns.
+    CreateIndex {
+        name: String,
+        table: String,
+        columns: Vec<String>,
+        unique: bool,
+    },
+    /// Drop a named secondary index.
+    DropIndex { name: String, if_exists: bool },
     /// Delete matching rows.
     Delete {
         table: String,
@@ -157,20 +242,24 @@ pub enum Statement {
         r#where: Option<Expression>,
     },
     /// Select matching rows.
-    Select {
-        select: Vec<(Expression, Option<String>)>, // optional column aliases
-        from: Vec<From>,
-        r#where: Option<Expression>,
-        group_by: Vec<Expression>,
-        having: Option<Expression>,
-        order_by: Vec<(Expression, Direction)>,
-        offset: Option<Expression>,
-        limit: Option<Expression>,
-    },
+    Select(Select),
+}
+
+/// A SELECT query. Also used for subqueries in expressions.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Select {
+    pub select: Vec<(Expression, Option<String>)>, // optional column aliases
+    pub from: Vec<From>,
+    pub r#where: Option<Expression>,
+    pub group_by: Vec<Expression>,
+    pub having: Option<Expression>,
+    pub order_by: Vec<(Expression, Direction)>,
+    pub offset: Option<Expression>,
+    pub limit: Option<Expression>,
 }
 
 /// A FROM item.
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum From {
     /// A table.
     Table { name: String, alias: Option<String> },
@@ -194,10 +283,25 @@ pub struct Column {
     pub unique: bool,
     pub index: bool,
     pub references: Option<String>,
+    pub on_delete: ReferenceAction,
+    pub on_update: ReferenceAction,
+}
+
+/// An ALTER TABLE schema change.
+#[derive(Debug)]
+pub enum SchemaChange {
+    /// Add a column.
+    AddColumn(Column),
+    /// Drop a column.
+    DropColumn { name: String, if_exists: bool },
+    /// Rename a column.
+    RenameColumn { from: String, to: String },
+    /// Rename the table.
+    RenameTable(String),
 }
 
 /// JOIN types.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum JoinType {
     Left,
     Right,
@@ -217,7 +321,7 @@ impl JoinType {
 }
 
 /// ORDER BY direction (default direction is [`Direction::Ascending`]).
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
 pub enum Direction {
     #[default]
     Ascending,
@@ -317,4 +421,11 @@ pub enum Operator {
 
     /// a LIKE b
     Like(Box<Expression>, Box<Expression>),
+
+    /// a IN (SELECT ...)
+    InSubquery(Box<Expression>, Box<Select>),
+    /// a IN (b, c, ...)
+    InList(Box<Expression>, Vec<Expression>),
+    /// a BETWEEN b AND c
+    Between(Box<Expression>, Box<Expression>, Box<Expression>),
 }
@@ -7,6 +7,7 @@ use std::{iter::Peekable, str::Chars};
 /// lexical tokens (e.g. keyword, number, string, etc.), which are passed on to
 /// the SQL parser. In doing so, it strips away basic syntactic noise such as
 /// whitespace, case, and quotes, and performs initial symbol validation.
+#[derive(Clone)]
 pub struct Lexer<'a> {
     chars: Peekable<Chars<'a>>,
 }
@@ -110,13 +111,19 @@ impl From<Keyword> for Token {
 /// Reserved SQL keywords.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Keyword {
+    Add,
+    Alter,
     And,
     As,
     Asc,
     Begin,
+    Between,
     Bool,
     Boolean,
     By,
+    Cascade,
+    Case,
+    Column,
     Commit,
     Create,
     Cross,
@@ -125,6 +132,8 @@ pub enum Keyword {
     Desc,
     Double,
     Drop,
+    Else,
+    End,
     Exists,
     Explain,
     False,
@@ -133,6 +142,7 @@ pub enum Keyword {
     Group,
     Having,
     If,
+    In,
     Index,
     Infinity,
     Inner,
@@ -159,6 +169,8 @@ pub enum Keyword {
     Primary,
     Read,
     References,
+    Rename,
+    Restrict,
     Right,
     Rollback,
     Select,
@@ -167,13 +179,16 @@ pub enum Keyword {
     System,
     Table,
     Text,
+    Then,
     Time,
+    To,
     Transaction,
     True,
     Unique,
     Update,
     Values,
     Varchar,
+    When,
     Where,
     Write,
 }
@@ -191,13 +206,19 @@ impl TryFrom<&str> for Keyword {
             "keyword must be lowercase"
         );
         Ok(match value.to_lowercase().as_str() {
+            "add" => Self::Add,
+            "alter" => Self::Alter,
             "as" => Self::As,
             "asc" => Self::Asc,
             "and" => Self::And,
             "begin" => Self::Begin,
+            "between" => Self::Between,
             "bool" => Self::Bool,
             "boolean" => Self::Boolean,
             "by" => Self::By,
+            "cascade" => Self::Cascade,
+            "case" => Self::Case,
+            "column" => Self::Column,
             "commit" => Self::Commit,
             "create" => Self::Create,
             "cross" => Self::Cross,
@@ -206,6 +227,8 @@ impl TryFrom<&str> for Keyword {
             "desc" => Self::Desc,
             "double" => Self::Double,
             "drop" => Self::Drop,
+            "else" => Self::Else,
+            "end" => Self::End,
             "exists" => Self::Exists,
             "explain" => Self::Explain,
             "false" => Self::False,
@@ -214,6 +237,7 @@ impl TryFrom<&str> for Keyword {
             "group" => Self::Group,
             "having" => Self::Having,
             "if" => Self::If,
+            "in" => Self::In,
             "index" => Self::Index,
             "infinity" => Self::Infinity,
             "inner" => Self::Inner,
@@ -240,6 +264,8 @@ impl TryFrom<&str> for Keyword {
             "primary" => Self::Primary,
             "read" => Self::Read,
             "references" => Self::References,
+            "rename" => Self::Rename,
+            "restrict" => Self::Restrict,
             "right" => Self::Right,
             "rollback" => Self::Rollback,
             "select" => Self::Select,
@@ -248,13 +274,16 @@ impl TryFrom<&str> for Keyword {
             "system" => Self::System,
             "table" => Self::Table,
             "text" => Self::Text,
+            "then" => Self::Then,
             "time" => Self::Time,
+            "to" => Self::To,
             "transaction" => Self::Transaction,
             "true" => Self::True,
             "unique" => Self::Unique,
             "update" => Self::Update,
             "values" => Self::Values,
             "varchar" => Self::Varchar,
+            "when" => Self::When,
             "where" => Self::Where,
             "write" => Self::Write,
             _ => return Err("not a keyword"),
@@ -266,13 +295,19 @@ impl std::fmt::Display for Keyword {
     /// Display keywords as uppercase.
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.write_str(match self {
+            Self::Add => "ADD",
+            Self::Alter => "ALTER",
             Self::As => "AS",
             Self::Asc => "ASC",
             Self::And => "AND",
             Self::Begin => "BEGIN",
+            Self::Between => "BETWEEN",
             Self::Bool => "BOOL",
             Self::Boolean => "BOOLEAN",
             Self::By => "BY",
+            Self::Cascade => "CASCADE",
+            Self::Case => "CASE",
+            Self::Column => "COLUMN",
             Self::Commit => "COMMIT",
             Self::Create => "CREATE",
             Self::Cross => "CROSS",
@@ -281,6 +316,8 @@ impl std::fmt::Display for Keyword {
             Self::Desc => "DESC",
             Self::Double => "DOUBLE",
             Self::Drop => "DROP",
+            Self::Else => "ELSE",
+            Self::End => "END",
             Self::Exists => "EXISTS",
             Self::Explain => "EXPLAIN",
             Self::False => "FALSE",
@@ -289,6 +326,7 @@ impl std::fmt::Display for Keyword {
             Self::Group => "GROUP",
             Self::Having => "HAVING",
             Self::If => "IF",
+            Self::In => "IN",
             Self::Index => "INDEX",
             Self::Infinity => "INFINITY",
             Self::Inner => "INNER",
@@ -315,6 +353,8 @@ impl std::fmt::Display for Keyword {
             Self::Primary => "PRIMARY",
             Self::Read => "READ",
             Self::References => "REFERENCES",
+            Self::Rename => "RENAME",
+            Self::Restrict => "RESTRICT",
             Self::Right => "RIGHT",
             Self::Rollback => "ROLLBACK",
             Self::Select => "SELECT",
@@ -323,13 +363,16 @@ impl std::fmt::Display for Keyword {
             Self::System => "SYSTEM",
             Self::Table => "TABLE",
             Self::Text => "TEXT",
+            Self::Then => "THEN",
             Self::Time => "TIME",
+            Self::To => "TO",
             Self::Transaction => "TRANSACTION",
             Self::True => "TRUE",
             Self::Unique => "UNIQUE",
             Self::Update => "UPDATE",
             Self::Values => "VALUES",
             Self::Varchar => "VARCHAR",
+            Self::When => "WHEN",
             Self::Where => "WHERE",
             Self::Write => "WRITE",
         })
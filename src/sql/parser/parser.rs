@@ -3,7 +3,7 @@
 use super::{ast, Keyword, Lexer, Token};
 use crate::errinput;
 use crate::error::Result;
-use crate::sql::types::DataType;
+use crate::sql::types::{DataType, ReferenceAction};
 
 /// The SQL parser takes tokens from the lexer and parses the SQL syntax into an
 /// Abstract Syntax Tree (AST). This nested structure represents the syntactic
@@ -17,7 +17,7 @@ pub struct Parser<'a> {
 
 impl Parser<'_> {
     /// Creates a new parser for the given raw SQL string.
-    pub fn new(statement: &str) -> Parser {
+    pub fn new(statement: &str) -> Parser<'_> {
         Parser {
             lexer: Lexer::new(statement).peekable(),
         }
@@ -102,6 +102,14 @@ impl Parser<'_> {
             .transpose()
     }
 
+    /// Peeks the token after the next lexer token, if any. This clones the
+    /// lexer, but it's only needed to disambiguate a few operators.
+    fn peek_second(&self) -> Result<Option<Token>> {
+        let mut lexer = self.lexer.clone();
+        lexer.next();
+        lexer.next().transpose()
+    }
+
     /// Parses a SQL statement.
     fn parse_statement(&mut self) -> Result<ast::Statement> {
         let Some(token) = self.peek()? else {
@@ -113,12 +121,13 @@ impl Parser<'_> {
             Token::Keyword(Keyword::Rollback) => self.parse_rollback(),
             Token::Keyword(Keyword::Explain) => self.parse_explain(),
 
-            Token::Keyword(Keyword::Create) => self.parse_create_table(),
-            Token::Keyword(Keyword::Drop) => self.parse_drop_table(),
+            Token::Keyword(Keyword::Create) => self.parse_create(),
+            Token::Keyword(Keyword::Drop) => self.parse_drop(),
+            Token::Keyword(Keyword::Alter) => self.parse_alter_table(),
 
             Token::Keyword(Keyword::Delete) => self.parse_delete(),
             Token::Keyword(Keyword::Insert) => self.parse_insert(),
-            Token::Keyword(Keyword::Select) => self.parse_select(),
+            Token::Keyword(Keyword::Select) => Ok(ast::Statement::Select(self.parse_select()?)),
             Token::Keyword(Keyword::Update) => self.parse_update(),
 
             invalid_token => errinput!("unexpected token `{invalid_token}`"),
@@ -173,21 +182,81 @@ impl Parser<'_> {
         Ok(ast::Statement::Explain(Box::new(self.parse_statement()?)))
     }
 
-    /// Parses a CREATE TABLE statement.
-    fn parse_create_table(&mut self) -> Result<ast::Statement> {
+    /// Parses a CREATE TABLE or CREATE INDEX statement.
+    fn parse_create(&mut self) -> Result<ast::Statement> {
         self.expect(Keyword::Create.into())?;
+        match self.peek()? {
+            Some(Token::Keyword(Keyword::Table)) => self.parse_create_table(),
+            Some(Token::Keyword(Keyword::Index | Keyword::Unique)) => self.parse_create_index(),
+            Some(token) => errinput!("unexpected token `{token}`"),
+            None => errinput!("unexpected end of input"),
+        }
+    }
+
+    /// Parses a DROP TABLE or DROP INDEX statement.
+    fn parse_drop(&mut self) -> Result<ast::Statement> {
+        self.expect(Keyword::Drop.into())?;
+        match self.peek()? {
+            Some(Token::Keyword(Keyword::Table)) => self.parse_drop_table(),
+            Some(Token::Keyword(Keyword::Index)) => self.parse_drop_index(),
+            Some(token) => errinput!("unexpected token `{token}`"),
+            None => errinput!("unexpected end of input"),
+        }
+    }
+
+    /// Parses an IF EXISTS clause, if present.
+    fn parse_if_exists(&mut self) -> Result<bool> {
+        if !self.next_is(Keyword::If.into()) {
+            return Ok(false);
+        }
+        self.expect(Keyword::Exists.into())?;
+        Ok(true)
+    }
+
+    /// Parses a CREATE TABLE statement, after CREATE.
+    fn parse_create_table(&mut self) -> Result<ast::Statement> {
         self.expect(Keyword::Table.into())?;
         let name = self.next_ident()?;
         self.expect(Token::OpenParen)?;
         let mut columns = Vec::new();
+        let mut primary_key = Vec::new();
         loop {
-            columns.push(self.parse_create_table_column()?);
+            if self.next_is(Keyword::Primary.into()) {
+                if !primary_key.is_empty() {
+                    return errinput!("primary key already set for table `{name}`");
+                }
+                self.expect(Keyword::Key.into())?;
+                primary_key = self.parse_ident_list()?;
+            } else {
+                columns.push(self.parse_create_table_column()?);
+            }
             if !self.next_is(Token::Comma) {
                 break;
             }
         }
         self.expect(Token::CloseParen)?;
-        Ok(ast::Statement::CreateTable { name, columns })
+        if !primary_key.is_empty() && columns.iter().any(|c| c.primary_key) {
+            return errinput!("primary key already set for table `{name}`");
+        }
+        Ok(ast::Statement::CreateTable {
+            name,
+            columns,
+            primary_key,
+        })
+    }
+
+    /// Parses a parenthesized, comma-separated list of identifiers.
+    fn parse_ident_list(&mut self) -> Result<Vec<String>> {
+        self.expect(Token::OpenParen)?;
+        let mut idents = Vec::new();
+        loop {
+            idents.push(self.next_ident()?);
+            if !self.next_is(Token::Comma) {
+                break;
+            }
+        }
+        self.expect(Token::CloseParen)?;
+        Ok(idents)
     }
 
     /// Parses a CREATE TABLE column definition.
@@ -209,6 +278,8 @@ impl Parser<'_> {
             unique: false,
             index: false,
             references: None,
+            on_delete: ReferenceAction::default(),
+            on_update: ReferenceAction::default(),
         };
         while let Some(keyword) = self.next_if_keyword() {
             match keyword {
@@ -232,26 +303,108 @@ impl Parser<'_> {
                 Keyword::Default => column.default = Some(self.parse_expression()?),
                 Keyword::Unique => column.unique = true,
                 Keyword::Index => column.index = true,
-                Keyword::References => column.references = Some(self.next_ident()?),
+                Keyword::References => {
+                    column.references = Some(self.next_ident()?);
+                    let (mut on_delete, mut on_update) = (None, None);
+                    while self.next_is(Keyword::On.into()) {
+                        let (action, keyword) = match self.next()? {
+                            Token::Keyword(Keyword::Delete) => (&mut on_delete, Keyword::Delete),
+                            Token::Keyword(Keyword::Update) => (&mut on_update, Keyword::Update),
+                            token => return errinput!("unexpected token `{token}`"),
+                        };
+                        if action.is_some() {
+                            return errinput!(
+                                "ON {keyword} already set for column `{}`",
+                                column.name
+                            );
+                        }
+                        *action = Some(self.parse_reference_action()?);
+                    }
+                    column.on_delete = on_delete.unwrap_or_default();
+                    column.on_update = on_update.unwrap_or_default();
+                }
                 keyword => return errinput!("unexpected keyword {keyword}"),
             }
         }
         Ok(column)
     }
 
-    /// Parses a DROP TABLE statement.
+    /// Parses a foreign key reference action: CASCADE, SET NULL or RESTRICT.
+    fn parse_reference_action(&mut self) -> Result<ReferenceAction> {
+        Ok(match self.next()? {
+            Token::Keyword(Keyword::Cascade) => ReferenceAction::Cascade,
+            Token::Keyword(Keyword::Restrict) => ReferenceAction::Restrict,
+            Token::Keyword(Keyword::Set) => {
+                self.expect(Keyword::Null.into())?;
+                ReferenceAction::SetNull
+            }
+            token => return errinput!("unexpected token `{token}`, wanted reference action"),
+        })
+    }
+
+    /// Parses a DROP TABLE statement, after DROP.
     fn parse_drop_table(&mut self) -> Result<ast::Statement> {
-        self.expect(Token::Keyword(Keyword::Drop))?;
         self.expect(Token::Keyword(Keyword::Table))?;
-        let mut if_exists = false;
-        if self.next_is(Keyword::If.into()) {
-            self.expect(Token::Keyword(Keyword::Exists))?;
-            if_exists = true;
-        }
+        let if_exists = self.parse_if_exists()?;
         let name = self.next_ident()?;
         Ok(ast::Statement::DropTable { name, if_exists })
     }
 
+    /// Parses a CREATE [UNIQUE] INDEX statement, after CREATE.
+    fn parse_create_index(&mut self) -> Result<ast::Statement> {
+        let unique = self.next_is(Keyword::Unique.into());
+        self.expect(Keyword::Index.into())?;
+        let name = self.next_ident()?;
+        self.expect(Keyword::On.into())?;
+        let table = self.next_ident()?;
+        let columns = self.parse_ident_list()?;
+        Ok(ast::Statement::CreateIndex {
+            name,
+            table,
+            columns,
+            unique,
+        })
+    }
+
+    /// Parses a DROP INDEX statement, after DROP.
+    fn parse_drop_index(&mut self) -> Result<ast::Statement> {
+        self.expect(Keyword::Index.into())?;
+        let if_exists = self.parse_if_exists()?;
+        let name = self.next_ident()?;
+        Ok(ast::Statement::DropIndex { name, if_exists })
+    }
+
+    /// Parses an ALTER TABLE statement.
+    fn parse_alter_table(&mut self) -> Result<ast::Statement> {
+        self.expect(Keyword::Alter.into())?;
+        self.expect(Keyword::Table.into())?;
+        let name = self.next_ident()?;
+        let change = match self.next()? {
+            Token::Keyword(Keyword::Add) => {
+                self.skip(Keyword::Column.into());
+                ast::SchemaChange::AddColumn(self.parse_create_table_column()?)
+            }
+            Token::Keyword(Keyword::Drop) => {
+                self.skip(Keyword::Column.into());
+                let if_exists = self.parse_if_exists()?;
+                let name = self.next_ident()?;
+                ast::SchemaChange::DropColumn { name, if_exists }
+            }
+            Token::Keyword(Keyword::Rename) if self.next_is(Keyword::To.into()) => {
+                ast::SchemaChange::RenameTable(self.next_ident()?)
+            }
+            Token::Keyword(Keyword::Rename) => {
+                self.skip(Keyword::Column.into());
+                let from = self.next_ident()?;
+                self.expect(Keyword::To.into())?;
+                let to = self.next_ident()?;
+                ast::SchemaChange::RenameColumn { from, to }
+            }
+            token => return errinput!("unexpected token `{token}`"),
+        };
+        Ok(ast::Statement::AlterTable { name, change })
+    }
+
     /// Parses a DELETE statement.
     fn parse_delete(&mut self) -> Result<ast::Statement> {
         self.expect(Keyword::Delete.into())?;
@@ -334,9 +487,9 @@ impl Parser<'_> {
         })
     }
 
-    /// Parses a SELECT statement.
-    fn parse_select(&mut self) -> Result<ast::Statement> {
-        Ok(ast::Statement::Select {
+    /// Parses a SELECT statement or subquery.
+    fn parse_select(&mut self) -> Result<ast::Select> {
+        Ok(ast::Select {
             select: self.parse_select_clause()?,
             from: self.parse_from_clause()?,
             r#where: self.parse_where_clause()?,
@@ -354,6 +507,14 @@ impl Parser<'_> {
         })
     }
 
+    /// Parses a parenthesized SELECT subquery.
+    fn parse_subquery(&mut self) -> Result<ast::Select> {
+        self.expect(Token::OpenParen)?;
+        let select = self.parse_select()?;
+        self.expect(Token::CloseParen)?;
+        Ok(select)
+    }
+
     /// Parses a SELECT clause, if present.
     fn parse_select_clause(&mut self) -> Result<Vec<(ast::Expression, Option<String>)>> {
         if !self.next_is(Keyword::Select.into()) {
@@ -523,16 +684,19 @@ impl Parser<'_> {
         while let Some(postfix) = self.parse_postfix_operator(min_precedence)? {
             lhs = postfix.build(lhs)
         }
-        // Apply any binary infix operators, parsing the right-hand operand.
-        while let Some(infix) = self.parse_infix_operator(min_precedence) {
-            let at_precedence = infix.precedence() + infix.associativity();
-            let rhs = self.parse_expression_at(at_precedence)?;
-            lhs = infix.build(lhs, rhs);
-        }
-        // Apply any postfix operators after the binary operator. Consider e.g.
-        // 1 + NULL IS NULL.
-        while let Some(postfix) = self.parse_postfix_operator(min_precedence)? {
-            lhs = postfix.build(lhs)
+        // Apply any binary infix operators, parsing the right-hand operand, and
+        // any postfix operators after them. Consider e.g. 1 + NULL IS NULL,
+        // which may in turn be followed by another infix operator.
+        loop {
+            if let Some(infix) = self.parse_infix_operator(min_precedence) {
+                let at_precedence = infix.precedence() + infix.associativity();
+                let rhs = self.parse_expression_at(at_precedence)?;
+                lhs = infix.build(lhs, rhs);
+            } else if let Some(postfix) = self.parse_postfix_operator(min_precedence)? {
+                lhs = postfix.build(lhs)
+            } else {
+                break;
+            }
         }
         Ok(lhs)
     }
@@ -543,6 +707,8 @@ impl Parser<'_> {
     /// * A column name.
     /// * A function call.
     /// * A parenthesized expression.
+    /// * A scalar subquery or EXISTS subquery.
+    /// * A CASE expression.
     fn parse_expression_atom(&mut self) -> Result<ast::Expression> {
         Ok(match self.next()? {
             // All columns.
@@ -578,13 +744,50 @@ impl Parser<'_> {
             }
             Token::Ident(column) => ast::Expression::Column(None, column),
 
-            // Parenthesized expression.
+            // Parenthesized expression or scalar subquery.
             Token::OpenParen => {
-                let expr = self.parse_expression()?;
+                let expr = match self.peek()? {
+                    Some(Token::Keyword(Keyword::Select)) => {
+                        ast::Expression::Subquery(Box::new(self.parse_select()?))
+                    }
+                    _ => self.parse_expression()?,
+                };
                 self.expect(Token::CloseParen)?;
                 expr
             }
 
+            // CASE expression, either simple (with operand) or searched.
+            Token::Keyword(Keyword::Case) => {
+                let mut operand = None;
+                if !matches!(self.peek()?, Some(Token::Keyword(Keyword::When))) {
+                    operand = Some(Box::new(self.parse_expression()?));
+                }
+                let mut when = Vec::new();
+                while self.next_is(Keyword::When.into()) {
+                    let condition = self.parse_expression()?;
+                    self.expect(Keyword::Then.into())?;
+                    when.push((condition, self.parse_expression()?));
+                }
+                if when.is_empty() {
+                    return errinput!("CASE must have at least one WHEN clause");
+                }
+                let r#else = self
+                    .next_is(Keyword::Else.into())
+                    .then(|| self.parse_expression().map(Box::new))
+                    .transpose()?;
+                self.expect(Keyword::End.into())?;
+                ast::Expression::Case {
+                    operand,
+                    when,
+                    r#else,
+                }
+            }
+
+            // EXISTS subquery.
+            Token::Keyword(Keyword::Exists) => {
+                ast::Expression::Exists(Box::new(self.parse_subquery()?))
+            }
+
             token => return errinput!("expected expression atom, found `{token}`"),
         })
     }
@@ -657,6 +860,60 @@ impl Parser<'_> {
             return Ok(Some(operator));
         }
 
+        // Handle [NOT] IN and [NOT] BETWEEN separately too. A NOT that isn't
+        // followed by either is left alone, e.g. DEFAULT 1 NOT NULL.
+        let is_in_or_between = |token: Option<&Token>| {
+            matches!(token, Some(Token::Keyword(Keyword::In | Keyword::Between)))
+        };
+        let operator = match self.peek()? {
+            Some(Token::Keyword(Keyword::Not)) => is_in_or_between(self.peek_second()?.as_ref()),
+            token => is_in_or_between(token),
+        };
+        if operator {
+            // IN and BETWEEN have the same precedence as IS.
+            let precedence = PostfixOperator::Is(ast::Literal::Null).precedence();
+            if precedence < min_precedence {
+                return Ok(None);
+            }
+            let not = self.next_is(Keyword::Not.into());
+            let operator = match self.next()? {
+                Token::Keyword(Keyword::In) => {
+                    self.expect(Token::OpenParen)?;
+                    let operator = match self.peek()? {
+                        Some(Token::Keyword(Keyword::Select)) => {
+                            PostfixOperator::InSubquery(Box::new(self.parse_select()?))
+                        }
+                        _ => {
+                            let mut list = Vec::new();
+                            loop {
+                                list.push(self.parse_expression()?);
+                                if !self.next_is(Token::Comma) {
+                                    break;
+                                }
+                            }
+                            PostfixOperator::InList(list)
+                        }
+                    };
+                    self.expect(Token::CloseParen)?;
+                    operator
+                }
+                // Parse the bounds above the BETWEEN precedence, such that the
+                // AND separates them rather than being parsed as an operator.
+                Token::Keyword(Keyword::Between) => {
+                    let low = self.parse_expression_at(precedence + LEFT_ASSOCIATIVE)?;
+                    self.expect(Keyword::And.into())?;
+                    let high = self.parse_expression_at(precedence + LEFT_ASSOCIATIVE)?;
+                    PostfixOperator::Between(low, high)
+                }
+                token => return errinput!("unexpected token `{token}`"),
+            };
+            let operator = match not {
+                false => operator,
+                true => PostfixOperator::Not(Box::new(operator)),
+            };
+            return Ok(Some(operator));
+        }
+
         Ok(self.next_if_map(|token| {
             let operator = match token {
                 Token::Exclamation => PostfixOperator::Factorial,
@@ -746,14 +1003,15 @@ enum InfixOperator {
 impl InfixOperator {
     /// The operator precedence.
     ///
-    /// Mostly follows Postgres, except IS and LIKE having same precedence as =.
+    /// Mostly follows Postgres, except IS, IN, BETWEEN and LIKE having same
+    /// precedence as =.
     /// This is similar to SQLite and MySQL.
     fn precedence(&self) -> Precedence {
         match self {
             Self::Or => 1,
             Self::And => 2,
             // Self::Not => 3
-            Self::Equal | Self::NotEqual | Self::Like => 4, // and Self::Is
+            Self::Equal | Self::NotEqual | Self::Like => 4, // and IS, IN, BETWEEN
             Self::GreaterThan
             | Self::GreaterThanOrEqual
             | Self::LessThan
@@ -803,13 +1061,26 @@ enum PostfixOperator {
     Is(ast::Literal),
     /// a IS NOT NULL | NAN
     IsNot(ast::Literal),
+    /// a IN (SELECT ...)
+    InSubquery(Box<ast::Select>),
+    /// a IN (b, c, ...)
+    InList(Vec<ast::Expression>),
+    /// a BETWEEN b AND c
+    Between(ast::Expression, ast::Expression),
+    /// a NOT IN ... | NOT BETWEEN ...
+    Not(Box<PostfixOperator>),
 }
 
 impl PostfixOperator {
     // The operator precedence.
     fn precedence(&self) -> Precedence {
         match self {
-            Self::Is(_) | Self::IsNot(_) => 4,
+            Self::Is(_)
+            | Self::IsNot(_)
+            | Self::InSubquery(_)
+            | Self::InList(_)
+            | Self::Between(_, _) => 4,
+            Self::Not(operator) => operator.precedence(),
             Self::Factorial => 9,
         }
     }
@@ -821,6 +1092,441 @@ impl PostfixOperator {
             Self::Factorial => ast::Operator::Factorial(lhs).into(),
             Self::Is(v) => ast::Operator::Is(lhs, v).into(),
             Self::IsNot(v) => ast::Operator::Not(ast::Operator::Is(lhs, v).into()).into(),
+            Self::InSubquery(select) => ast::Operator::InSubquery(lhs, select).into(),
+            Self::InList(list) => ast::Operator::InList(lhs, list).into(),
+            Self::Between(low, high) => {
+                ast::Operator::Between(lhs, Box::new(low), Box::new(high)).into()
+            }
+            Self::Not(operator) => ast::Operator::Not(operator.build(*lhs).into()).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses a statement.
+    fn parse(statement: &str) -> Result<ast::Statement> {
+        Parser::new(statement).parse()
+    }
+
+    /// Parses a CREATE TABLE statement, returning its columns.
+    fn parse_columns(statement: &str) -> Vec<ast::Column> {
+        match parse(statement) {
+            Ok(ast::Statement::CreateTable { columns, .. }) => columns,
+            result => panic!("unexpected result {result:?}"),
+        }
+    }
+
+    #[test]
+    fn reference_actions() {
+        use ReferenceAction::{Cascade, Restrict, SetNull};
+
+        // Actions default to RESTRICT, and can be given in either order.
+        for (reference, on_delete, on_update) in [
+            ("REFERENCES u", Restrict, Restrict),
+            ("REFERENCES u ON DELETE CASCADE", Cascade, Restrict),
+            ("REFERENCES u ON UPDATE SET NULL", Restrict, SetNull),
+            (
+                "REFERENCES u ON DELETE SET NULL ON UPDATE CASCADE",
+                SetNull,
+                Cascade,
+            ),
+            (
+                "REFERENCES u ON UPDATE RESTRICT ON DELETE CASCADE",
+                Cascade,
+                Restrict,
+            ),
+            ("REFERENCES u ON DELETE CASCADE NOT NULL", Cascade, Restrict),
+        ] {
+            let columns = parse_columns(&format!(
+                "CREATE TABLE t (id INT PRIMARY KEY, a INT {reference})"
+            ));
+            assert_eq!(columns[1].references.as_deref(), Some("u"), "{reference}");
+            assert_eq!(columns[1].on_delete, on_delete, "{reference}");
+            assert_eq!(columns[1].on_update, on_update, "{reference}");
+        }
+
+        // Each action can only be given once, and must be a known action.
+        for reference in [
+            "REFERENCES u ON DELETE CASCADE ON DELETE RESTRICT",
+            "REFERENCES u ON UPDATE CASCADE ON DELETE CASCADE ON UPDATE CASCADE",
+            "REFERENCES u ON DELETE SET",
+            "REFERENCES u ON DELETE NULL",
+            "REFERENCES u ON DELETE",
+            "REFERENCES u ON INSERT CASCADE",
+            "REFERENCES ON DELETE CASCADE",
+        ] {
+            let statement = format!("CREATE TABLE t (id INT PRIMARY KEY, a INT {reference})");
+            assert!(parse(&statement).is_err(), "{reference} parsed");
+        }
+    }
+
+    /// Parses an ALTER TABLE statement on table t, returning its change.
+    fn parse_alter(statement: &str) -> ast::SchemaChange {
+        match parse(statement) {
+            Ok(ast::Statement::AlterTable { name, change }) if name == "t" => change,
+            result => panic!("unexpected result {result:?}"),
+        }
+    }
+
+    #[test]
+    fn alter_table() {
+        // ADD takes a column definition, with an optional COLUMN keyword.
+        for statement in [
+            "ALTER TABLE t ADD COLUMN a INT DEFAULT 1 NOT NULL",
+            "ALTER TABLE t ADD a INT DEFAULT 1 NOT NULL",
+        ] {
+            let ast::SchemaChange::AddColumn(column) = parse_alter(statement) else {
+                panic!("expected ADD COLUMN");
+            };
+            assert_eq!(column.name, "a");
+            assert_eq!(column.default, Some(ast::Literal::Integer(1).into()));
+            assert_eq!(column.nullable, Some(false));
+        }
+
+        // DROP takes an optional COLUMN keyword and IF EXISTS.
+        assert!(matches!(
+            parse_alter("ALTER TABLE t DROP COLUMN a"),
+            ast::SchemaChange::DropColumn { name, if_exists: false } if name == "a"
+        ));
+        assert!(matches!(
+            parse_alter("ALTER TABLE t DROP IF EXISTS a"),
+            ast::SchemaChange::DropColumn { name, if_exists: true } if name == "a"
+        ));
+
+        // RENAME TO renames the table, otherwise a column.
+        assert!(matches!(
+            parse_alter("ALTER TABLE t RENAME TO u"),
+            ast::SchemaChange::RenameTable(name) if name == "u"
+        ));
+        for statement in [
+            "ALTER TABLE t RENAME COLUMN a TO b",
+            "ALTER TABLE t RENAME a TO b",
+        ] {
+            assert!(matches!(
+                parse_alter(statement),
+                ast::SchemaChange::RenameColumn { from, to } if from == "a" && to == "b"
+            ));
+        }
+
+        // Other changes, and incomplete ones, are errors.
+        for statement in [
+            "ALTER TABLE t",
+            "ALTER TABLE t ALTER COLUMN a",
+            "ALTER TABLE t ADD COLUMN a",
+            "ALTER TABLE t RENAME a",
+            "ALTER TABLE t RENAME TO",
+            "ALTER t RENAME TO u",
+        ] {
+            assert!(parse(statement).is_err(), "{statement} parsed");
+        }
+    }
+
+    #[test]
+    fn create_table_primary_key() {
+        // A composite primary key is given as a table constraint, in any
+        // position.
+        for statement in [
+            "CREATE TABLE t (a INT, b STRING, PRIMARY KEY (a, b))",
+            "CREATE TABLE t (PRIMARY KEY (a, b), a INT, b STRING)",
+        ] {
+            let Ok(ast::Statement::CreateTable {
+                columns,
+                primary_key,
+                ..
+            }) = parse(statement)
+            else {
+                panic!("{statement} didn't parse");
+            };
+            assert_eq!(primary_key, vec!["a".to_string(), "b".to_string()]);
+            assert!(columns.iter().all(|c| !c.primary_key), "{statement}");
+        }
+
+        // A single-column primary key is given inline, which leaves the
+        // table constraint empty.
+        let Ok(ast::Statement::CreateTable { primary_key, .. }) =
+            parse("CREATE TABLE t (a INT PRIMARY KEY, b STRING)")
+        else {
+            panic!("inline primary key didn't parse");
+        };
+        assert!(primary_key.is_empty());
+
+        // The primary key can only be given once.
+        for statement in [
+            "CREATE TABLE t (a INT, b INT, PRIMARY KEY (a), PRIMARY KEY (b))",
+            "CREATE TABLE t (a INT PRIMARY KEY, b INT, PRIMARY KEY (a, b))",
+            "CREATE TABLE t (PRIMARY KEY (a, b), a INT, b INT PRIMARY KEY)",
+            "CREATE TABLE t (a INT, PRIMARY KEY ())",
+            "CREATE TABLE t (a INT, PRIMARY KEY a)",
+        ] {
+            assert!(parse(statement).is_err(), "{statement} parsed");
+        }
+    }
+
+    #[test]
+    fn create_index() {
+        for (statement, unique, expect) in [
+            ("CREATE INDEX i ON t (a)", false, vec!["a"]),
+            ("CREATE UNIQUE INDEX i ON t (a)", true, vec!["a"]),
+            ("CREATE INDEX i ON t (a, b)", false, vec!["a", "b"]),
+            (
+                "CREATE UNIQUE INDEX i ON t (b, a, c)",
+                true,
+                vec!["b", "a", "c"],
+            ),
+        ] {
+            assert!(
+                matches!(
+                    parse(statement),
+                    Ok(ast::Statement::CreateIndex { name, table, columns, unique: u })
+                        if name == "i" && table == "t" && columns == expect && u == unique
+                ),
+                "{statement}"
+            );
+        }
+
+        for statement in [
+            "CREATE INDEX i ON t",
+            "CREATE INDEX i ON t ()",
+            "CREATE INDEX i ON t (a,)",
+            "CREATE INDEX i ON t (a b)",
+            "CREATE INDEX ON t (a)",
+            "CREATE UNIQUE i ON t (a)",
+            "CREATE INDEX i t (a)",
+        ] {
+            assert!(parse(statement).is_err(), "{statement} parsed");
+        }
+    }
+
+    #[test]
+    fn drop_index() {
+        for (statement, if_exists) in [("DROP INDEX i", false), ("DROP INDEX IF EXISTS i", true)] {
+            assert!(
+                matches!(
+                    parse(statement),
+                    Ok(ast::Statement::DropIndex { name, if_exists: e })
+                        if name == "i" && e == if_exists
+                ),
+                "{statement}"
+            );
+        }
+
+        for statement in ["DROP INDEX", "DROP INDEX IF i", "DROP i"] {
+            assert!(parse(statement).is_err(), "{statement} parsed");
+        }
+    }
+
+    #[test]
+    fn create_table_not_null_after_default() {
+        let columns =
+            parse_columns("CREATE TABLE t (id INT PRIMARY KEY, a INT DEFAULT 1 NOT NULL)");
+        assert_eq!(columns[1].default, Some(ast::Literal::Integer(1).into()));
+        assert_eq!(columns[1].nullable, Some(false));
+
+        let columns =
+            parse_columns("CREATE TABLE t (id INT PRIMARY KEY, a INT DEFAULT 1 NOT NULL UNIQUE)");
+        assert_eq!(columns[1].nullable, Some(false));
+        assert!(columns[1].unique);
+
+        // Literals other than NULL can't follow NOT.
+        assert!(parse("CREATE TABLE t (id INT PRIMARY KEY, a INT DEFAULT 1 NOT 2)").is_err());
+    }
+
+    /// Parses an expression.
+    fn parse_expr(expr: &str) -> ast::Expression {
+        let mut parser = Parser::new(expr);
+        let expr = parser.parse_expression().expect("parse failed");
+        assert!(parser.lexer.next().is_none(), "unparsed input in {expr:?}");
+        expr
+    }
+
+    /// Parses a SELECT statement.
+    fn parse_select(statement: &str) -> ast::Select {
+        match parse(statement) {
+            Ok(ast::Statement::Select(select)) => select,
+            result => panic!("unexpected result {result:?}"),
+        }
+    }
+
+    /// Builds a column reference expression.
+    fn column(table: Option<&str>, name: &str) -> ast::Expression {
+        ast::Expression::Column(table.map(String::from), name.into())
+    }
+
+    /// Builds an integer literal expression.
+    fn integer(i: i64) -> ast::Expression {
+        ast::Literal::Integer(i).into()
+    }
+
+    #[test]
+    fn scalar_subquery() {
+        let ast::Expression::Operator(ast::Operator::Add(lhs, rhs)) =
+            parse_expr("(SELECT MAX(b) FROM t) + 1")
+        else {
+            panic!("expected +");
+        };
+        assert_eq!(
+            *lhs,
+            ast::Expression::Subquery(parse_select("SELECT MAX(b) FROM t").into())
+        );
+        assert_eq!(*rhs, integer(1));
+
+        // A parenthesized expression is not a subquery.
+        assert_eq!(parse_expr("(1)"), integer(1));
+    }
+
+    #[test]
+    fn exists_subquery() {
+        let select = parse_select("SELECT 1 FROM t WHERE a = 1");
+        assert_eq!(
+            parse_expr("EXISTS (SELECT 1 FROM t WHERE a = 1)"),
+            ast::Expression::Exists(select.clone().into())
+        );
+        assert_eq!(
+            parse_expr("NOT EXISTS (SELECT 1 FROM t WHERE a = 1)"),
+            ast::Operator::Not(ast::Expression::Exists(select.into()).into()).into()
+        );
+
+        // EXISTS requires a subquery.
+        assert!(Parser::new("EXISTS (1)").parse_expression().is_err());
+        assert!(Parser::new("EXISTS t").parse_expression().is_err());
+    }
+
+    /// Outer column references in subqueries are ordinary qualified columns.
+    #[test]
+    fn correlated_subquery() {
+        let select = parse_select(
+            "SELECT name FROM users u WHERE EXISTS (SELECT 1 FROM posts p WHERE p.author = u.id)",
+        );
+        let Some(ast::Expression::Exists(subquery)) = select.r#where else {
+            panic!("expected EXISTS");
+        };
+        assert_eq!(
+            subquery.r#where,
+            Some(
+                ast::Operator::Equal(
+                    column(Some("p"), "author").into(),
+                    column(Some("u"), "id").into()
+                )
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn not_in_subquery() {
+        let ast::Expression::Operator(ast::Operator::Or(lhs, rhs)) =
+            parse_expr("a NOT IN (SELECT b FROM t) OR c")
+        else {
+            panic!("expected OR");
+        };
+        let ast::Expression::Operator(ast::Operator::Not(not)) = *lhs else {
+            panic!("expected NOT");
+        };
+        assert!(matches!(
+            *not,
+            ast::Expression::Operator(ast::Operator::InSubquery(_, _))
+        ));
+        assert_eq!(*rhs, column(None, "c"));
+    }
+
+    /// Infix operators can follow postfix operators.
+    #[test]
+    fn infix_after_postfix() {
+        let null = ast::Expression::from(ast::Literal::Null);
+        let add = ast::Operator::Add(integer(1).into(), null.into());
+        let is_null = ast::Operator::Is(add.into(), ast::Literal::Null);
+        assert_eq!(
+            parse_expr("1 + NULL IS NULL OR b"),
+            ast::Operator::Or(is_null.into(), column(None, "b").into()).into()
+        );
+    }
+
+    #[test]
+    fn between_precedence() {
+        use ast::Operator::{And, Between, Not};
+        let a = || Box::new(column(None, "a"));
+        let b = || Box::new(column(None, "b"));
+        let int = |i| Box::new(integer(i));
+
+        // The first AND after BETWEEN separates its bounds, later ones are
+        // logical.
+        assert_eq!(
+            parse_expr("a BETWEEN 1 AND 2 AND b"),
+            And(Between(a(), int(1), int(2)).into(), b()).into()
+        );
+        assert_eq!(
+            parse_expr("a NOT BETWEEN 1 AND 2 AND b"),
+            And(Not(Between(a(), int(1), int(2)).into()).into(), b()).into()
+        );
+        assert_eq!(
+            parse_expr("b AND a BETWEEN 1 AND 2"),
+            And(b(), Between(a(), int(1), int(2)).into()).into()
+        );
+
+        // Bounds bind tighter than comparisons, but looser than arithmetic.
+        assert_eq!(
+            parse_expr("a BETWEEN 1 + 1 AND 2 * 2"),
+            Between(
+                a(),
+                ast::Operator::Add(int(1), int(1)).into(),
+                ast::Operator::Multiply(int(2), int(2)).into(),
+            )
+            .into()
+        );
+        assert!(Parser::new("a BETWEEN 1 OR 2").parse_expression().is_err());
+    }
+
+    #[test]
+    fn in_list_precedence() {
+        use ast::Operator::{InList, Not, Or};
+        let a = || Box::new(column(None, "a"));
+        let list = || vec![integer(1), integer(2)];
+
+        assert_eq!(
+            parse_expr("a IN (1, 2) OR b"),
+            Or(InList(a(), list()).into(), column(None, "b").into()).into()
+        );
+        assert_eq!(
+            parse_expr("a NOT IN (1, 2) OR b"),
+            Or(
+                Not(InList(a(), list()).into()).into(),
+                column(None, "b").into()
+            )
+            .into()
+        );
+        assert_eq!(
+            parse_expr("NOT a IN (1, 2)"),
+            Not(InList(a(), list()).into()).into()
+        );
+        assert!(Parser::new("a IN ()").parse_expression().is_err());
+    }
+
+    #[test]
+    fn case() {
+        assert_eq!(
+            parse_expr("CASE a WHEN 1 THEN 2 WHEN 3 THEN 4 END"),
+            ast::Expression::Case {
+                operand: Some(column(None, "a").into()),
+                when: vec![(integer(1), integer(2)), (integer(3), integer(4))],
+                r#else: None,
+            }
+        );
+        assert_eq!(
+            parse_expr("CASE WHEN a THEN 1 ELSE 2 END"),
+            ast::Expression::Case {
+                operand: None,
+                when: vec![(column(None, "a"), integer(1))],
+                r#else: Some(integer(2).into()),
+            }
+        );
+        for expr in ["CASE END", "CASE a ELSE 1 END", "CASE WHEN a THEN 1"] {
+            assert!(
+                Parser::new(expr).parse_expression().is_err(),
+                "{expr} parsed"
+            );
         }
     }
 }
@@ -55,6 +55,66 @@ pub enum Expression {
 
     // Checks if a string matches a pattern: a LIKE b.
     Like(Box<Expression>, Box<Expression>),
+
+    /// A CASE expression: CASE [a] WHEN b THEN c ... ELSE d END. Without an
+    /// operand a, this is a searched CASE, which returns the result c of the
+    /// first condition b that is true. With an operand, this is a simple CASE,
+    /// which evaluates the operand once and returns the result of the first
+    /// value b equal to it. A NULL condition or comparison is not true, so a
+    /// NULL operand never matches. Returns the else result d if nothing
+    /// matches. COALESCE and NULLIF are built as searched CASE, see the
+    /// constructors below.
+    Case(
+        Option<Box<Expression>>,
+        Vec<(Expression, Expression)>,
+        Box<Expression>,
+    ),
+}
+
+impl Expression {
+    /// Builds COALESCE(a, b, ...), which returns the first non-NULL argument,
+    /// or NULL if all are NULL. Equivalent to CASE WHEN a IS NOT NULL THEN a
+    /// WHEN b IS NOT NULL THEN b ... ELSE NULL END.
+    pub fn coalesce(args: Vec<Expression>) -> Self {
+        let when = args.into_iter().map(|arg| {
+            let condition = Self::Not(Self::Is(arg.clone().into(), Value::Null).into());
+            (condition, arg)
+        });
+        Self::Case(None, when.collect(), Value::Null.into())
+    }
+
+    /// Builds NULLIF(a, b), which returns NULL if a = b, otherwise a.
+    /// Equivalent to CASE WHEN a = b THEN NULL ELSE a END, so it returns a if
+    /// either is NULL.
+    pub fn nullif(lhs: Expression, rhs: Expression) -> Self {
+        let condition = Self::Equal(lhs.clone().into(), rhs.into());
+        Self::Case(None, vec![(condition, Value::Null.into())], lhs.into())
+    }
+
+    /// Builds a BETWEEN b AND c, as (a > b OR a = b) AND (a < c OR a = c). As
+    /// with other comparisons, it returns NULL rather than false if any value
+    /// is NULL, unless the other bound comparison is false.
+    pub fn between(expr: Expression, low: Expression, high: Expression) -> Self {
+        let at_least = Self::Or(
+            Self::GreaterThan(expr.clone().into(), low.clone().into()).into(),
+            Self::Equal(expr.clone().into(), low.into()).into(),
+        );
+        let at_most = Self::Or(
+            Self::LessThan(expr.clone().into(), high.clone().into()).into(),
+            Self::Equal(expr.into(), high.into()).into(),
+        );
+        Self::And(at_least.into(), at_most.into())
+    }
+
+    /// Builds a IN (b, c, ...), as a = b OR a = c OR .... It returns true if
+    /// any value matches, otherwise NULL if a or any value is NULL, otherwise
+    /// false. An empty list is false.
+    pub fn in_list(expr: Expression, list: Vec<Expression>) -> Self {
+        list.into_iter()
+            .map(|value| Self::Equal(expr.clone().into(), value.into()))
+            .reduce(|lhs, rhs| Self::Or(lhs.into(), rhs.into()))
+            .unwrap_or(Value::Boolean(false).into())
+    }
 }
 
 impl From<Value> for Expression {
@@ -68,3 +128,70 @@ impl From<Value> for Box<Expression> {
         Box::new(value.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Expression::*;
+
+    /// Returns a boxed constant expression.
+    fn c(value: impl Into<Value>) -> Box<Expression> {
+        Box::new(Constant(value.into()))
+    }
+
+    /// Returns a boxed column reference expression.
+    fn col(index: usize) -> Box<Expression> {
+        Box::new(Column(index))
+    }
+
+    #[test]
+    fn in_list() {
+        // NULL IN (1, NULL) compares each value, and is thus NULL.
+        assert_eq!(
+            Expression::in_list(*c(Value::Null), vec![*c(1), *c(Value::Null)]),
+            Or(
+                Equal(c(Value::Null), c(1)).into(),
+                Equal(c(Value::Null), c(Value::Null)).into()
+            )
+        );
+        assert_eq!(
+            Expression::in_list(*col(0), vec![*c(1)]),
+            Equal(col(0), c(1))
+        );
+        assert_eq!(Expression::in_list(*col(0), Vec::new()), *c(false));
+    }
+
+    #[test]
+    fn between() {
+        assert_eq!(
+            Expression::between(*col(0), *c(1), *c(2)),
+            And(
+                Or(GreaterThan(col(0), c(1)).into(), Equal(col(0), c(1)).into()).into(),
+                Or(LessThan(col(0), c(2)).into(), Equal(col(0), c(2)).into()).into()
+            )
+        );
+    }
+
+    #[test]
+    fn coalesce() {
+        assert_eq!(
+            Expression::coalesce(vec![*col(0), *c(1)]),
+            Case(
+                None,
+                vec![
+                    (Not(Is(col(0), Value::Null).into()), *col(0)),
+                    (Not(Is(c(1), Value::Null).into()), *c(1)),
+                ],
+                c(Value::Null)
+            )
+        );
+    }
+
+    #[test]
+    fn nullif() {
+        assert_eq!(
+            Expression::nullif(*col(0), *c(1)),
+            Case(None, vec![(Equal(col(0), c(1)), *c(Value::Null))], col(0))
+        );
+    }
+}
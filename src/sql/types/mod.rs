@@ -7,4 +7,5 @@ mod schema;
 mod value;
 
 pub use expression::Expression;
+pub use schema::{Column, Index, ReferenceAction, SchemaChange, Table};
 pub use value::{DataType, Label, Row, Rows, Value};
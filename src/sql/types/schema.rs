@@ -1,22 +1,33 @@
-use super::{DataType, Value};
+use super::{DataType, Row, Value};
 use crate::encoding;
+use crate::error::Result;
+use crate::sql::engine::{Catalog, Transaction};
+use crate::{errdata, errinput};
 
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::collections::HashSet;
 
 /// A table schema, which specifies its data structure and constraints.
 ///
-/// Tables can't change after they are created. There is no ALTER TABLE nor
-/// CREATE/DROP INDEX -- only CREATE TABLE and DROP TABLE.
+/// Tables can be changed after they are created via ALTER TABLE, which adds,
+/// drops or renames columns or renames the table, and via CREATE/DROP INDEX
+/// (see [`SchemaChange`]). Each change increments the schema version.
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Table {
     /// The table name. Can't be empty.
     pub name: String,
-    /// The primary key column index. A table must have a primary key, and it
-    /// can only be a single column.
-    pub primary_key: usize,
+    /// The schema version. Starts at 0 when the table is created, and is
+    /// incremented by every schema change.
+    pub version: u64,
+    /// The primary key column indexes, in key order. A table must have a
+    /// primary key, and it can span multiple columns (a composite key). Rows
+    /// are identified and ordered by their primary key value tuple.
+    pub primary_key: Vec<usize>,
     /// The table's columns. Must have at least one.
     pub columns: Vec<Column>,
+    /// The table's named secondary indexes.
+    pub indexes: Vec<Index>,
 }
 
 impl encoding::Value for Table {}
@@ -28,22 +39,97 @@ pub struct Column {
     pub name: String,
     /// Column datatype.
     pub datatype: DataType,
-    /// Whether the column allows null values. Not legal for primary keys.
+    /// Whether the column allows null values. Not legal for primary key
+    /// columns.
     pub nullable: bool,
     /// The column's default value. If None, the user must specify an explicit
     /// value. Must match the column datatype. Nullable columns require a
     /// default (often Null), and Null is only a valid default when nullable.
     pub default: Option<Value>,
     /// Whether the column should only allow unique values (ignoring NULLs).
-    /// Must be true for a primary key column.
+    /// Must be true for a single-column primary key.
     pub unique: bool,
-    /// Whether the column should have a secondary index. Must be false for
-    /// primary keys, which are the implicit primary index. Must be true for
-    /// unique or reference columns.
+    /// Whether the column should have a secondary index. Must be false for a
+    /// single-column primary key, which is the implicit primary index. Must be
+    /// true for unique or reference columns.
     pub index: bool,
     /// If set, this column is a foreign key reference to the given table's
-    /// primary key. Must be of the same type as the target primary key.
+    /// primary key. The target primary key must be a single column of the same
+    /// type.
     pub references: Option<String>,
+    /// The action to take on this column when a referenced row is deleted.
+    /// Only used if references is set.
+    pub on_delete: ReferenceAction,
+    /// The action to take on this column when a referenced row's primary key
+    /// is changed. Only used if references is set.
+    pub on_update: ReferenceAction,
+}
+
+/// A foreign key reference action, taken on referencing rows when the
+/// referenced row is deleted or its primary key changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub enum ReferenceAction {
+    /// Reject the change while the row is referenced.
+    #[default]
+    Restrict,
+    /// Delete the referencing rows, or update their references to the new
+    /// primary key.
+    Cascade,
+    /// Set the references to NULL. The column must be nullable.
+    SetNull,
+}
+
+impl std::fmt::Display for ReferenceAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Restrict => "RESTRICT",
+            Self::Cascade => "CASCADE",
+            Self::SetNull => "SET NULL",
+        })
+    }
+}
+
+/// A named secondary index, created by CREATE INDEX. Column indexes declared
+/// in CREATE TABLE are instead given by [`Column::index`], and are named after
+/// the column.
+///
+/// Multi-column indexes are keyed by the tuple of column values, so they can
+/// be used for lookups by a leading prefix of their columns.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Index {
+    /// The index name. Must be unique across all tables, and can't be the name
+    /// of a column in the table.
+    pub name: String,
+    /// The indexed column names, in key order. Must have at least one.
+    pub columns: Vec<String>,
+    /// Whether the indexed value tuples must be unique (ignoring tuples
+    /// containing NULLs).
+    pub unique: bool,
+    /// Whether the index has been backfilled. Writes maintain the index
+    /// regardless, but it can only be used for lookups once ready.
+    pub ready: bool,
+}
+
+/// A schema change applied to an existing table by ALTER TABLE, or by CREATE
+/// and DROP INDEX.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum SchemaChange {
+    /// Adds a column. Existing rows get the column's default value, which
+    /// must be set.
+    AddColumn(Column),
+    /// Drops a column by name. Can't be a primary key column.
+    DropColumn(String),
+    /// Renames a column.
+    RenameColumn(String, String),
+    /// Renames the table.
+    RenameTable(String),
+    /// Adds a named index. It must not be ready: existing rows are backfilled
+    /// separately, see [`crate::sql::engine::Engine::build_index`].
+    CreateIndex(Index),
+    /// Marks a backfilled index as ready for lookups.
+    IndexReady(String),
+    /// Drops a named index.
+    DropIndex(String),
 }
 
 impl std::fmt::Display for Table {
@@ -51,7 +137,7 @@ impl std::fmt::Display for Table {
         writeln!(f, "CREATE TABLE {} (", format_ident(&self.name))?;
         for (i, column) in self.columns.iter().enumerate() {
             write!(f, "  {} {}", format_ident(&column.name), column.datatype)?;
-            if i == self.primary_key {
+            if self.primary_key == [i] {
                 write!(f, " PRIMARY KEY")?;
             } else if !column.nullable {
                 write!(f, " NOT NULL")?;
@@ -59,7 +145,7 @@ impl std::fmt::Display for Table {
             if let Some(default) = &column.default {
                 write!(f, " DEFAULT {default}")?;
             }
-            if i != self.primary_key {
+            if self.primary_key != [i] {
                 if column.unique {
                     write!(f, " UNIQUE")?;
                 }
@@ -69,20 +155,978 @@ impl std::fmt::Display for Table {
             }
             if let Some(reference) = &column.references {
                 write!(f, " REFERENCES {reference}")?;
+                if column.on_delete != ReferenceAction::default() {
+                    write!(f, " ON DELETE {}", column.on_delete)?;
+                }
+                if column.on_update != ReferenceAction::default() {
+                    write!(f, " ON UPDATE {}", column.on_update)?;
+                }
             }
-            if i < self.columns.len() - 1 {
+            if i < self.columns.len() - 1 || self.primary_key.len() > 1 {
                 write!(f, ",")?;
             }
             writeln!(f)?;
         }
-        write!(f, ")")
+        if self.primary_key.len() > 1 {
+            let columns = self.primary_key.iter().map(|i| &self.columns[*i].name);
+            writeln!(f, "  PRIMARY KEY ({})", format_idents(columns))?;
+        }
+        write!(f, ")")?;
+        for index in &self.indexes {
+            let unique = if index.unique { "UNIQUE " } else { "" };
+            write!(
+                f,
+                ";\nCREATE {unique}INDEX {} ON {} ({})",
+                format_ident(&index.name),
+                format_ident(&self.name),
+                format_idents(&index.columns)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Table {
+    /// Validates the table schema, using the catalog to validate foreign key
+    /// references. Called when the table is created.
+    pub fn validate(&self, catalog: &impl Catalog) -> Result<()> {
+        self.validate_as(&self.name, catalog)
+    }
+
+    /// Validates the table schema, where the catalog may already contain the
+    /// table under the given name.
+    fn validate_as(&self, catalog_name: &str, catalog: &impl Catalog) -> Result<()> {
+        if self.name.is_empty() {
+            return errinput!("table name can't be empty");
+        }
+        if self.columns.is_empty() {
+            return errinput!("table {} has no columns", self.name);
+        }
+        if self.primary_key.is_empty() {
+            return errinput!("table {} has no primary key", self.name);
+        }
+        for (i, c) in self.primary_key.iter().enumerate() {
+            if self.columns.get(*c).is_none() {
+                return errdata!("invalid primary key index {c}");
+            }
+            if self.primary_key[..i].contains(c) {
+                return errinput!("duplicate primary key column {}", self.columns[*c].name);
+            }
+        }
+
+        for (i, column) in self.columns.iter().enumerate() {
+            let (cname, ctype) = (&column.name, &column.datatype);
+            if cname.is_empty() {
+                return errinput!("column name can't be empty");
+            }
+            if self.columns[..i].iter().any(|c| &c.name == cname) {
+                return errinput!("duplicate column {cname}");
+            }
+
+            // Validate primary key. Columns of a composite primary key aren't
+            // unique by themselves, and may have their own indexes.
+            if self.primary_key.contains(&i) && column.nullable {
+                return errinput!("primary key {cname} can't be nullable");
+            }
+            let is_primary_key = self.primary_key == [i];
+            if is_primary_key {
+                if !column.unique {
+                    return errdata!("primary key {cname} must be unique");
+                }
+                if column.index {
+                    return errdata!("primary key {cname} can't have an index");
+                }
+            }
+
+            // Validate default value.
+            match column.default.as_ref().map(|v| v.datatype()) {
+                None if column.nullable => {
+                    return errinput!("nullable column {cname} must have a default value");
+                }
+                Some(None) if !column.nullable => {
+                    return errinput!("invalid NULL default for non-nullable column {cname}");
+                }
+                Some(Some(vtype)) if vtype != *ctype => {
+                    return errinput!("invalid default type {vtype} for {ctype} column {cname}");
+                }
+                Some(_) | None => {}
+            }
+
+            // Validate unique index.
+            if column.unique && !column.index && !is_primary_key {
+                return errdata!("unique column {cname} must have a secondary index");
+            }
+
+            // Validate references.
+            if let Some(reference) = &column.references {
+                if !column.index && !is_primary_key {
+                    return errdata!("reference column {cname} must have a secondary index");
+                }
+                let reftypes: Vec<DataType> = if reference == &self.name {
+                    self.primary_key_columns().map(|c| c.datatype).collect()
+                } else if let Some(target) = catalog.get_table(reference)? {
+                    target.primary_key_columns().map(|c| c.datatype).collect()
+                } else {
+                    return errinput!("unknown table {reference} referenced by column {cname}");
+                };
+                let [reftype] = reftypes[..] else {
+                    return errinput!(
+                        "can't reference composite primary key of table {reference} from column {cname}"
+                    );
+                };
+                if *ctype != reftype {
+                    return errinput!(
+                        "can't reference {reftype} primary key of table {reference} from {ctype} column {cname}"
+                    );
+                }
+                for action in [column.on_delete, column.on_update] {
+                    if action == ReferenceAction::SetNull && !column.nullable {
+                        return errinput!("{action} not allowed for non-nullable column {cname}");
+                    }
+                }
+            }
+        }
+
+        // Validate named indexes. Index names must be unique across tables.
+        let mut index_names: HashSet<String> = HashSet::new();
+        if !self.indexes.is_empty() {
+            let tables = catalog.list_tables()?.into_iter();
+            let indexes = tables
+                .filter(|t| t.name != catalog_name)
+                .flat_map(|t| t.indexes);
+            index_names.extend(indexes.map(|i| i.name));
+        }
+        for (i, index) in self.indexes.iter().enumerate() {
+            let iname = &index.name;
+            if iname.is_empty() {
+                return errinput!("index name can't be empty");
+            }
+            if !index_names.insert(iname.clone()) {
+                return errinput!("index {iname} already exists");
+            }
+            if self.column_index(iname).is_ok() {
+                return errinput!("index name {iname} can't be a column name");
+            }
+            if index.columns.is_empty() {
+                return errinput!("index {iname} has no columns");
+            }
+            let columns = index
+                .columns
+                .iter()
+                .map(|c| self.column_index(c))
+                .collect::<Result<Vec<_>>>()?;
+            for (j, c) in index.columns.iter().enumerate() {
+                if index.columns[..j].contains(c) {
+                    return errinput!("duplicate column {c} in index {iname}");
+                }
+            }
+            if columns == self.primary_key
+                || matches!(columns.as_slice(), [c] if self.columns[*c].index)
+                || self.indexes[..i].iter().any(|o| o.columns == index.columns)
+            {
+                let columns = format_idents(&index.columns);
+                return errinput!("columns ({columns}) are already indexed");
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates a schema returned by [`Table::alter`] for the given change to
+    /// the old schema, like [`Table::validate`]. Also errors if a renamed table
+    /// already exists, or if an added column's default value is invalid for the
+    /// existing rows: a non-NULL unique default when there are several rows,
+    /// or a non-NULL reference default that doesn't exist.
+    pub fn validate_alter(
+        &self,
+        old: &Table,
+        change: &SchemaChange,
+        txn: &(impl Transaction + Catalog),
+    ) -> Result<()> {
+        if self.name != old.name && txn.get_table(&self.name)?.is_some() {
+            return errinput!("table {} already exists", self.name);
+        }
+        self.validate_as(&old.name, txn)?;
+
+        let SchemaChange::AddColumn(column) = change else {
+            return Ok(());
+        };
+        let Some(default) = column.default.as_ref().filter(|v| !v.is_undefined()) else {
+            return Ok(());
+        };
+        let cname = &column.name;
+        if column.unique && txn.scan(&old.name, None)?.take(2).count() > 1 {
+            return errinput!(
+                "can't add unique column {cname} with default {default} to several rows"
+            );
+        }
+        if let Some(target) = &column.references {
+            if txn.get(target, &[vec![default.clone()]])?.is_empty() {
+                return errinput!(
+                    "default reference {default} for column {cname} not in table {target}"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the named index, if it exists.
+    pub fn index(&self, name: &str) -> Option<&Index> {
+        self.indexes.iter().find(|i| i.name == name)
+    }
+
+    /// Returns a row's values for the given index columns, in index order.
+    pub fn index_values(&self, index: &Index, row: &[Value]) -> Result<Vec<Value>> {
+        index
+            .columns
+            .iter()
+            .map(|c| Ok(row[self.column_index(c)?].clone()))
+            .collect()
+    }
+
+    /// Returns the primary key columns, in key order.
+    pub fn primary_key_columns(&self) -> impl Iterator<Item = &Column> {
+        self.primary_key.iter().map(|i| &self.columns[*i])
+    }
+
+    /// Returns a row's primary key, as a tuple of its primary key values.
+    pub fn row_id(&self, row: &[Value]) -> Vec<Value> {
+        self.primary_key.iter().map(|i| row[*i].clone()).collect()
+    }
+
+    /// Returns the index of the given column, or errors if it doesn't exist.
+    pub fn column_index(&self, name: &str) -> Result<usize> {
+        self.columns
+            .iter()
+            .position(|c| c.name == name)
+            .ok_or_else(|| errinput!("unknown column {name} in table {}", self.name))
+    }
+
+    /// Applies a schema change, returning the new schema with an incremented
+    /// version. The new schema must also be validated with
+    /// [`Table::validate_alter`].
+    pub fn alter(&self, change: &SchemaChange) -> Result<Table> {
+        let mut table = self.clone();
+        table.version += 1;
+        match change {
+            SchemaChange::AddColumn(column) => {
+                if self.column_index(&column.name).is_ok() {
+                    return errinput!("column {} already exists", column.name);
+                }
+                if column.default.is_none() {
+                    return errinput!("added column {} must have a default value", column.name);
+                }
+                table.columns.push(column.clone());
+            }
+            SchemaChange::DropColumn(name) => {
+                let index = self.column_index(name)?;
+                if self.primary_key.contains(&index) {
+                    return errinput!("can't drop primary key column {name}");
+                }
+                if let Some(i) = self.indexes.iter().find(|i| i.columns.contains(name)) {
+                    return errinput!("can't drop column {name} used by index {}", i.name);
+                }
+                table.columns.remove(index);
+                for c in table.primary_key.iter_mut().filter(|c| **c > index) {
+                    *c -= 1;
+                }
+            }
+            SchemaChange::RenameColumn(from, to) => {
+                let index = self.column_index(from)?;
+                if self.column_index(to).is_ok() {
+                    return errinput!("column {to} already exists");
+                }
+                table.columns[index].name = to.clone();
+                let columns = table.indexes.iter_mut().flat_map(|i| &mut i.columns);
+                for column in columns.filter(|c| *c == from) {
+                    *column = to.clone();
+                }
+            }
+            SchemaChange::RenameTable(name) => {
+                for column in &mut table.columns {
+                    if column.references.as_ref() == Some(&self.name) {
+                        column.references = Some(name.clone());
+                    }
+                }
+                table.name = name.clone();
+            }
+            SchemaChange::CreateIndex(index) => {
+                if self.index(&index.name).is_some() {
+                    return errinput!("index {} already exists", index.name);
+                }
+                if index.ready {
+                    return errdata!("new index {} can't be ready", index.name);
+                }
+                table.indexes.push(index.clone());
+            }
+            SchemaChange::IndexReady(name) => {
+                match table.indexes.iter_mut().find(|i| &i.name == name) {
+                    Some(index) => index.ready = true,
+                    None => return errinput!("index {name} does not exist"),
+                }
+            }
+            SchemaChange::DropIndex(name) => {
+                if self.index(name).is_none() {
+                    return errinput!("index {name} does not exist");
+                }
+                table.indexes.retain(|i| &i.name != name);
+            }
+        }
+        Ok(table)
+    }
+
+    /// Rewrites a row of this table to match the schema returned by
+    /// [`Table::alter`] for the given change.
+    pub fn alter_row(&self, change: &SchemaChange, mut row: Row) -> Result<Row> {
+        match change {
+            SchemaChange::AddColumn(column) => {
+                row.push(column.default.clone().unwrap_or(Value::Null))
+            }
+            SchemaChange::DropColumn(name) => {
+                row.remove(self.column_index(name)?);
+            }
+            SchemaChange::RenameColumn(_, _)
+            | SchemaChange::RenameTable(_)
+            | SchemaChange::CreateIndex(_)
+            | SchemaChange::IndexReady(_)
+            | SchemaChange::DropIndex(_) => {}
+        }
+        Ok(row)
     }
+
+    /// Validates a row, including uniqueness checks and references. Called
+    /// for every row written to the table.
+    ///
+    /// For an update, old_id is the primary key of the row being replaced,
+    /// which may differ from the new row's primary key. For an insert, it is
+    /// None.
+    ///
+    /// Validating uniqueness and references individually for each written row
+    /// is not very efficient, it would be better to do this in bulk. But we
+    /// keep it simple.
+    pub fn validate_row(
+        &self,
+        row: &[Value],
+        old_id: Option<&[Value]>,
+        txn: &impl Transaction,
+    ) -> Result<()> {
+        if row.len() != self.columns.len() {
+            return errinput!("invalid row size for table {}", self.name);
+        }
+
+        // Validate primary key.
+        let id = self.row_id(row);
+        if let Some(value) = id.iter().find(|v| v.is_undefined()) {
+            return errinput!("invalid primary key value {value}");
+        }
+        if old_id != Some(id.as_slice())
+            && !txn.get(&self.name, std::slice::from_ref(&id))?.is_empty()
+        {
+            return errinput!("primary key {} already exists", Value::format_tuple(&id));
+        }
+
+        for (i, (column, value)) in self.columns.iter().zip(row).enumerate() {
+            let (cname, ctype) = (&column.name, &column.datatype);
+
+            // Validate datatype and nullability.
+            if let Some(vtype) = value.datatype() {
+                if vtype != *ctype {
+                    return errinput!("invalid datatype {vtype} for {ctype} column {cname}");
+                }
+            }
+            if value == &Value::Null && !column.nullable {
+                return errinput!("NULL value not allowed for column {cname}");
+            }
+
+            // Validate uniqueness constraints. Unique columns are indexed.
+            // NULL and NaN are never equal to each other, so they're ignored.
+            if column.unique && self.primary_key != [i] && !value.is_undefined() {
+                let values = [vec![value.clone()]];
+                let mut ids = txn.lookup_index(&self.name, cname, &values)?;
+                if let Some(old_id) = old_id {
+                    ids.remove(old_id); // ignore the existing version of this row
+                }
+                if !ids.is_empty() {
+                    return errinput!("value {value} already in unique column {cname}");
+                }
+            }
+        }
+
+        // Validate uniqueness constraints of named indexes. Indexes that aren't
+        // ready yet may miss rows that haven't been backfilled, but the backfill
+        // checks those rows against the index, dropping it on duplicates.
+        for index in self.indexes.iter().filter(|i| i.unique) {
+            let values = self.index_values(index, row)?;
+            if values.iter().any(|v| v.is_undefined()) {
+                continue;
+            }
+            let values = [values];
+            let mut ids = txn.lookup_index(&self.name, &index.name, &values)?;
+            if let Some(old_id) = old_id {
+                ids.remove(old_id);
+            }
+            if !ids.is_empty() {
+                let values = Value::format_tuple(&values[0]);
+                return errinput!("value {values} already in unique index {}", index.name);
+            }
+        }
+
+        // Validate outgoing references.
+        self.validate_references(row, txn)
+    }
+
+    /// Validates that a row's foreign key references exist. NULL references
+    /// and references to the row itself are always valid.
+    pub fn validate_references(&self, row: &[Value], txn: &impl Transaction) -> Result<()> {
+        let id = self.row_id(row);
+        for (column, value) in self.columns.iter().zip(row) {
+            let Some(target) = &column.references else {
+                continue;
+            };
+            match value {
+                Value::Null => {}
+                v if target == &self.name && id == [v.clone()] => {}
+                v => {
+                    if txn.get(target, &[vec![v.clone()]])?.is_empty() {
+                        return errinput!(
+                            "reference {v} in {}.{} not in table {target}",
+                            self.name,
+                            column.name
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats a list of identifiers as valid SQL, separated by commas.
+fn format_idents<'a>(idents: impl IntoIterator<Item = &'a String>) -> String {
+    let idents: Vec<_> = idents.into_iter().map(|i| format_ident(i)).collect();
+    idents.join(", ")
 }
 
 /// Formats an identifier as valid SQL, quoting it if necessary.
-fn format_ident(ident: &str) -> Cow<str> {
+fn format_ident(ident: &str) -> Cow<'_, str> {
     if crate::sql::parser::is_ident(ident) {
         return ident.into();
     }
     format!("\"{}\"", ident.replace('\"', "\"\"")).into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::sql::engine::test::TestTransaction;
+    use crate::sql::types::Row;
+
+    use std::collections::BTreeMap;
+
+    /// Returns a non-nullable column without constraints.
+    fn column(name: &str, datatype: DataType) -> Column {
+        Column {
+            name: name.into(),
+            datatype,
+            nullable: false,
+            default: None,
+            unique: false,
+            index: false,
+            references: None,
+            on_delete: ReferenceAction::Restrict,
+            on_update: ReferenceAction::Restrict,
+        }
+    }
+
+    /// CREATE TABLE users (id INTEGER PRIMARY KEY, name STRING UNIQUE INDEX,
+    /// email STRING NULL DEFAULT NULL)
+    fn users() -> Table {
+        Table {
+            name: "users".into(),
+            version: 0,
+            primary_key: vec![0],
+            columns: vec![
+                Column {
+                    unique: true,
+                    ..column("id", DataType::Integer)
+                },
+                Column {
+                    unique: true,
+                    index: true,
+                    ..column("name", DataType::String)
+                },
+                Column {
+                    nullable: true,
+                    default: Some(Value::Null),
+                    ..column("email", DataType::String)
+                },
+            ],
+            indexes: Vec::new(),
+        }
+    }
+
+    /// CREATE TABLE posts (id INTEGER PRIMARY KEY, author INTEGER INDEX
+    /// REFERENCES users, title STRING)
+    fn posts() -> Table {
+        Table {
+            name: "posts".into(),
+            version: 0,
+            primary_key: vec![0],
+            columns: vec![
+                Column {
+                    unique: true,
+                    ..column("id", DataType::Integer)
+                },
+                Column {
+                    index: true,
+                    references: Some("users".into()),
+                    ..column("author", DataType::Integer)
+                },
+                column("title", DataType::String),
+            ],
+            indexes: Vec::new(),
+        }
+    }
+
+    /// Returns a test transaction with the users and posts tables, and users
+    /// 1 alice and 2 bob.
+    fn setup() -> TestTransaction {
+        let txn = TestTransaction::with_tables([users(), posts()]).expect("setup failed");
+        let rows = vec![
+            vec![1.into(), "alice".into(), Value::Null],
+            vec![2.into(), "bob".into(), "bob@example.com".into()],
+        ];
+        txn.insert("users", rows).expect("setup failed");
+        txn
+    }
+
+    /// Asserts that validating the posts schema, as modified by the given
+    /// closure, fails with the given error.
+    macro_rules! test_validate_error {
+        ( $( $name:ident: $mutate:expr => $error:expr, )* ) => {
+        $(
+            #[test]
+            fn $name() {
+                let txn = TestTransaction::with_tables([users()]).expect("setup failed");
+                let mut table = posts();
+                let mutate: fn(&mut Table) = $mutate;
+                mutate(&mut table);
+                assert_eq!(table.validate(&txn), Err($error));
+            }
+        )*
+        };
+    }
+
+    test_validate_error! {
+        validate_empty_name: |t| t.name = "".into()
+            => Error::InvalidInput("table name can't be empty".into()),
+        validate_no_columns: |t| t.columns.clear()
+            => Error::InvalidInput("table posts has no columns".into()),
+        validate_no_primary_key: |t| t.primary_key.clear()
+            => Error::InvalidInput("table posts has no primary key".into()),
+        validate_invalid_primary_key: |t| t.primary_key = vec![3]
+            => Error::InvalidData("invalid primary key index 3".into()),
+        validate_duplicate_primary_key: |t| t.primary_key = vec![0, 0]
+            => Error::InvalidInput("duplicate primary key column id".into()),
+        validate_duplicate_column: |t| t.columns[2].name = "id".into()
+            => Error::InvalidInput("duplicate column id".into()),
+        validate_nullable_primary_key: |t| {
+            t.columns[0].nullable = true;
+            t.columns[0].default = Some(Value::Null);
+        } => Error::InvalidInput("primary key id can't be nullable".into()),
+        validate_nullable_composite_primary_key: |t| {
+            t.primary_key = vec![0, 2];
+            t.columns[0].unique = false;
+            t.columns[2].nullable = true;
+            t.columns[2].default = Some(Value::Null);
+        } => Error::InvalidInput("primary key title can't be nullable".into()),
+        validate_primary_key_not_unique: |t| t.columns[0].unique = false
+            => Error::InvalidData("primary key id must be unique".into()),
+        validate_primary_key_index: |t| t.columns[0].index = true
+            => Error::InvalidData("primary key id can't have an index".into()),
+        validate_nullable_without_default: |t| t.columns[2].nullable = true
+            => Error::InvalidInput("nullable column title must have a default value".into()),
+        validate_null_default_not_nullable: |t| t.columns[2].default = Some(Value::Null)
+            => Error::InvalidInput("invalid NULL default for non-nullable column title".into()),
+        validate_wrong_default_type: |t| t.columns[2].default = Some(1.into())
+            => Error::InvalidInput("invalid default type INTEGER for STRING column title".into()),
+        validate_unique_without_index: |t| t.columns[2].unique = true
+            => Error::InvalidData("unique column title must have a secondary index".into()),
+        validate_reference_without_index: |t| t.columns[1].index = false
+            => Error::InvalidData("reference column author must have a secondary index".into()),
+        validate_reference_unknown_table: |t| t.columns[1].references = Some("foo".into())
+            => Error::InvalidInput("unknown table foo referenced by column author".into()),
+        validate_reference_type_mismatch: |t| t.columns[1].datatype = DataType::String
+            => Error::InvalidInput(
+                "can't reference INTEGER primary key of table users from STRING column author".into()
+            ),
+        validate_reference_composite_key: |t| {
+            t.primary_key = vec![0, 1];
+            t.columns[0].unique = false;
+            t.columns[1].references = Some("posts".into());
+        } => Error::InvalidInput(
+            "can't reference composite primary key of table posts from column author".into()
+        ),
+        validate_set_null_not_nullable: |t| t.columns[1].on_delete = ReferenceAction::SetNull
+            => Error::InvalidInput("SET NULL not allowed for non-nullable column author".into()),
+    }
+
+    #[test]
+    fn validate() -> Result<()> {
+        let txn = TestTransaction::with_tables([users()])?;
+        posts().validate(&txn)?;
+
+        // SET NULL is allowed for nullable columns.
+        let mut table = posts();
+        table.columns[1].nullable = true;
+        table.columns[1].default = Some(Value::Null);
+        table.columns[1].on_delete = ReferenceAction::SetNull;
+        table.validate(&txn)?;
+
+        // Columns of a composite primary key aren't unique by themselves.
+        let mut table = posts();
+        table.primary_key = vec![0, 2];
+        table.columns[0].unique = false;
+        table.validate(&txn)
+    }
+
+    #[test]
+    fn validate_row() -> Result<()> {
+        let txn = setup();
+        let insert = |row: Row| txn.insert("posts", vec![row]);
+        let invalid = |msg: &str| Err(Error::InvalidInput(msg.into()));
+
+        insert(vec![1.into(), 1.into(), "hello".into()])?;
+        assert_eq!(
+            insert(vec![1.into(), 2.into()]),
+            invalid("invalid row size for table posts")
+        );
+        assert_eq!(
+            insert(vec![Value::Null, 1.into(), "a".into()]),
+            invalid("invalid primary key value NULL")
+        );
+        assert_eq!(
+            insert(vec![1.into(), 2.into(), "a".into()]),
+            invalid("primary key 1 already exists")
+        );
+        assert_eq!(
+            insert(vec![2.into(), "1".into(), "a".into()]),
+            invalid("invalid datatype STRING for INTEGER column author")
+        );
+        assert_eq!(
+            insert(vec![2.into(), Value::Null, "a".into()]),
+            invalid("NULL value not allowed for column author")
+        );
+        assert_eq!(
+            insert(vec![2.into(), 9.into(), "a".into()]),
+            invalid("reference 9 in posts.author not in table users")
+        );
+
+        // Unique columns ignore NULLs, but not other duplicates.
+        let row = vec![3.into(), "alice".into(), Value::Null];
+        assert_eq!(
+            txn.insert("users", vec![row]),
+            invalid("value 'alice' already in unique column name")
+        );
+        txn.insert("users", vec![vec![3.into(), "carol".into(), Value::Null]])?;
+        Ok(())
+    }
+
+    #[test]
+    fn validate_row_update() -> Result<()> {
+        let txn = setup();
+        let update =
+            |id: i64, row: Row| txn.update("users", BTreeMap::from([(vec![id.into()], row)]));
+
+        // Updating a row in place ignores its own unique values.
+        update(
+            1,
+            vec![1.into(), "alice".into(), "alice@example.com".into()],
+        )?;
+
+        // So does changing its primary key, but the new key must not exist.
+        update(1, vec![3.into(), "alice".into(), Value::Null])?;
+        assert_eq!(
+            update(3, vec![2.into(), "alice".into(), Value::Null]),
+            Err(Error::InvalidInput("primary key 2 already exists".into()))
+        );
+
+        // Other rows' unique values still conflict.
+        assert_eq!(
+            update(3, vec![3.into(), "bob".into(), Value::Null]),
+            Err(Error::InvalidInput(
+                "value 'bob' already in unique column name".into()
+            ))
+        );
+        Ok(())
+    }
+
+    /// Returns the posts table with a named index on title.
+    fn posts_with_index() -> Table {
+        let mut table = posts();
+        table.indexes.push(Index {
+            name: "posts_title".into(),
+            columns: vec!["title".into()],
+            unique: false,
+            ready: true,
+        });
+        table
+    }
+
+    /// Asserts that applying the given schema change to the posts table, with
+    /// a named index on title, fails with the given error.
+    macro_rules! test_alter_error {
+        ( $( $name:ident: $change:expr => $error:expr, )* ) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!(posts_with_index().alter(&$change), Err($error));
+            }
+        )*
+        };
+    }
+
+    test_alter_error! {
+        alter_add_duplicate_column: SchemaChange::AddColumn(Column {
+            default: Some("".into()),
+            ..column("title", DataType::String)
+        }) => Error::InvalidInput("column title already exists".into()),
+        alter_add_column_without_default:
+            SchemaChange::AddColumn(column("body", DataType::String))
+            => Error::InvalidInput("added column body must have a default value".into()),
+        alter_drop_unknown_column: SchemaChange::DropColumn("body".into())
+            => Error::InvalidInput("unknown column body in table posts".into()),
+        alter_drop_primary_key_column: SchemaChange::DropColumn("id".into())
+            => Error::InvalidInput("can't drop primary key column id".into()),
+        alter_drop_indexed_column: SchemaChange::DropColumn("title".into())
+            => Error::InvalidInput("can't drop column title used by index posts_title".into()),
+        alter_rename_unknown_column: SchemaChange::RenameColumn("body".into(), "text".into())
+            => Error::InvalidInput("unknown column body in table posts".into()),
+        alter_rename_duplicate_column:
+            SchemaChange::RenameColumn("author".into(), "title".into())
+            => Error::InvalidInput("column title already exists".into()),
+        alter_create_existing_index: SchemaChange::CreateIndex(Index {
+            ready: false,
+            ..posts_with_index().indexes[0].clone()
+        }) => Error::InvalidInput("index posts_title already exists".into()),
+        alter_create_ready_index: SchemaChange::CreateIndex(Index {
+            name: "posts_author".into(),
+            columns: vec!["author".into()],
+            unique: false,
+            ready: true,
+        }) => Error::InvalidData("new index posts_author can't be ready".into()),
+        alter_unknown_index_ready: SchemaChange::IndexReady("foo".into())
+            => Error::InvalidInput("index foo does not exist".into()),
+        alter_drop_unknown_index: SchemaChange::DropIndex("foo".into())
+            => Error::InvalidInput("index foo does not exist".into()),
+    }
+
+    #[test]
+    fn alter_add_column() -> Result<()> {
+        let table = posts();
+        let body = Column {
+            nullable: true,
+            default: Some(Value::Null),
+            ..column("body", DataType::String)
+        };
+        let change = SchemaChange::AddColumn(body.clone());
+        let new = table.alter(&change)?;
+        assert_eq!(new.version, table.version + 1);
+        assert_eq!(new.columns.last(), Some(&body));
+
+        // Existing rows get the default value.
+        let row = table.alter_row(&change, vec![1.into(), 1.into(), "a".into()])?;
+        assert_eq!(row, vec![1.into(), 1.into(), "a".into(), Value::Null]);
+        Ok(())
+    }
+
+    #[test]
+    fn alter_add_column_default() -> Result<()> {
+        let txn = setup();
+        let add =
+            |table: &str, column: Column| txn.alter_table(table, SchemaChange::AddColumn(column));
+        let unique = Column {
+            unique: true,
+            index: true,
+            default: Some(7.into()),
+            ..column("u", DataType::Integer)
+        };
+        let reference = Column {
+            index: true,
+            references: Some("users".into()),
+            default: Some(99.into()),
+            ..column("r", DataType::Integer)
+        };
+
+        // A unique default would be duplicated across users 1 and 2, unless
+        // it's NULL, but it's fine for the single post.
+        assert_eq!(
+            add("users", unique.clone()),
+            Err(Error::InvalidInput(
+                "can't add unique column u with default 7 to several rows".into()
+            ))
+        );
+        let nullable = Column {
+            nullable: true,
+            default: Some(Value::Null),
+            ..unique.clone()
+        };
+        add("users", nullable)?;
+        txn.insert("posts", vec![vec![1.into(), 1.into(), "a".into()]])?;
+        add("posts", unique)?;
+
+        // A reference default must exist.
+        assert_eq!(
+            add("posts", reference.clone()),
+            Err(Error::InvalidInput(
+                "default reference 99 for column r not in table users".into()
+            ))
+        );
+        add(
+            "posts",
+            Column {
+                default: Some(2.into()),
+                ..reference
+            },
+        )?;
+        assert_eq!(
+            txn.get("posts", &[vec![1.into()]])?,
+            vec![vec![1.into(), 1.into(), "a".into(), 7.into(), 2.into()]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn alter_drop_column() -> Result<()> {
+        // Use a composite primary key (id, title), which must be shifted down
+        // past the dropped author column.
+        let mut table = posts();
+        table.primary_key = vec![0, 2];
+        table.columns[0].unique = false;
+        let change = SchemaChange::DropColumn("author".into());
+        let new = table.alter(&change)?;
+        assert_eq!(new.primary_key, vec![0, 1]);
+        let names: Vec<_> = new.primary_key_columns().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "title"]);
+
+        let row = table.alter_row(&change, vec![1.into(), 1.into(), "a".into()])?;
+        assert_eq!(row, vec![1.into(), "a".into()]);
+        assert_eq!(new.row_id(&row), vec![1.into(), "a".into()]);
+        Ok(())
+    }
+
+    #[test]
+    fn alter_rename_column() -> Result<()> {
+        let table = posts_with_index();
+        let change = SchemaChange::RenameColumn("title".into(), "heading".into());
+        let new = table.alter(&change)?;
+        assert_eq!(new.columns[2].name, "heading");
+        assert_eq!(new.indexes[0].columns, vec!["heading".to_string()]);
+
+        // Rows are unchanged.
+        let row = vec![1.into(), 1.into(), "a".into()];
+        assert_eq!(table.alter_row(&change, row.clone())?, row);
+        Ok(())
+    }
+
+    #[test]
+    fn alter_rename_table() -> Result<()> {
+        // Add a self-reference, which must follow the rename, unlike the
+        // reference to users.
+        let mut table = posts();
+        table.columns.push(Column {
+            nullable: true,
+            default: Some(Value::Null),
+            index: true,
+            references: Some("posts".into()),
+            ..column("parent", DataType::Integer)
+        });
+        let new = table.alter(&SchemaChange::RenameTable("articles".into()))?;
+        assert_eq!(new.name, "articles");
+        assert_eq!(new.columns[1].references, Some("users".into()));
+        assert_eq!(new.columns[3].references, Some("articles".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn alter_rename_referenced_table() -> Result<()> {
+        let txn = setup();
+        txn.insert("posts", vec![vec![1.into(), 1.into(), "a".into()]])?;
+
+        // Renaming users updates the posts reference, and bumps its version.
+        let table = txn.alter_table("users", SchemaChange::RenameTable("people".into()))?;
+        assert_eq!(table.version, 1);
+        let posts = txn.must_get_table("posts")?;
+        assert_eq!(posts.columns[1].references, Some("people".into()));
+        assert_eq!(posts.version, 1);
+        assert_eq!(txn.get_table("users")?, None);
+        assert_eq!(txn.get("people", &[vec![1.into()]])?.len(), 1);
+
+        // Renaming to an existing table fails.
+        assert_eq!(
+            txn.alter_table("people", SchemaChange::RenameTable("posts".into())),
+            Err(Error::InvalidInput("table posts already exists".into()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn validate_rename_table_with_index() -> Result<()> {
+        let txn = TestTransaction::with_tables([users()])?;
+        let index = Index {
+            name: "users_email".into(),
+            columns: vec!["email".into()],
+            unique: false,
+            ready: false,
+        };
+        txn.alter_table("users", SchemaChange::CreateIndex(index.clone()))?;
+
+        // The table's own index doesn't conflict with itself after a rename.
+        let table = txn.alter_table("users", SchemaChange::RenameTable("people".into()))?;
+        assert_eq!(table.index("users_email"), Some(&index));
+        assert_eq!(
+            txn.get_index_table("users_email")?.map(|t| t.name),
+            Some("people".into())
+        );
+
+        // Index names must still be unique across tables.
+        txn.create_table(users())?;
+        assert_eq!(
+            txn.alter_table("users", SchemaChange::CreateIndex(index)),
+            Err(Error::InvalidInput(
+                "index users_email already exists".into()
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn validate_row_unique_index() -> Result<()> {
+        let txn = setup();
+        let index = Index {
+            name: "users_email".into(),
+            columns: vec!["email".into()],
+            unique: true,
+            ready: false,
+        };
+        txn.alter_table("users", SchemaChange::CreateIndex(index))?;
+
+        // Indexes that aren't ready only see rows written since they were
+        // created, here the ones written below.
+        txn.insert(
+            "users",
+            vec![vec![3.into(), "carol".into(), "bob@example.com".into()]],
+        )?;
+        assert_eq!(
+            txn.insert(
+                "users",
+                vec![vec![4.into(), "dave".into(), "bob@example.com".into()]]
+            ),
+            Err(Error::InvalidInput(
+                "value 'bob@example.com' already in unique index users_email".into()
+            ))
+        );
+        txn.insert("users", vec![vec![4.into(), "dave".into(), Value::Null]])?;
+        Ok(())
+    }
+}
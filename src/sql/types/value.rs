@@ -255,6 +255,18 @@ impl Value {
     pub fn is_normalized(&self) -> bool {
         matches!(self.normalize_ref(), Cow::Borrowed(_))
     }
+
+    /// Formats a value tuple, e.g. a composite primary key, as a parenthesized
+    /// comma-separated list. A single value is formatted as-is.
+    pub fn format_tuple(values: &[Value]) -> String {
+        match values {
+            [value] => value.to_string(),
+            values => {
+                let values: Vec<_> = values.iter().map(|v| v.to_string()).collect();
+                format!("({})", values.join(", "))
+            }
+        }
+    }
 }
 
 impl std::fmt::Display for Value {